@@ -1,6 +1,10 @@
 // This example uses a dummy SPI/GPIO implementation to test what happens when
 // the `dw3000_ng` driver is initialized.
-use dw3000_ng::{hl::SendTime, Config, DW3000};
+use dw3000_ng::{
+    hl::SendTime,
+    ll::trace::{self, Transaction},
+    Config, DW3000,
+};
 
 use embedded_hal_bus::spi::ExclusiveDevice;
 
@@ -31,12 +35,12 @@ struct DummySpi {
 }
 
 impl DummySpi {
-    /// Decode the SPI message header generated by `init_header`
-    fn decode_header(header: &[u8]) -> (u8, u8, bool) {
-        let write = header[0] & 0x80 != 0;
-        let id = (header[0] & 0x3e) >> 1;
-        let sub_id = ((header[0] & 0x01) << 6) | (header[1] >> 2);
-        (id, sub_id, write)
+    /// Returns the register address of a register access
+    fn address(transaction: &Result<Transaction, trace::DecodeError>) -> Option<(u8, u8)> {
+        match transaction {
+            Ok(Transaction::Register(access)) => Some((access.id, access.sub_id)),
+            _ => None,
+        }
     }
 }
 
@@ -61,28 +65,23 @@ impl hal::spi::SpiBus<u8> for DummySpi {
 
     #[maybe_async_attr]
     async fn write(&mut self, _data: &[u8]) -> Result<(), Self::Error> {
-        if _data.len() == 1 {
-            // This is a SPI fast command
-            log::info!("SPI fast command: {:02x}", _data[0]);
-            return Ok(());
-        }
+        let transaction = trace::decode(_data, &[], false);
 
-        let (id, sub_id, write) = DummySpi::decode_header(_data);
-
-        if id == 0x11 && sub_id == 0x08 && self.state == SimulatedState::Startup {
+        if DummySpi::address(&transaction) == Some((0x11, 0x08))
+            && self.state == SimulatedState::Startup
+        {
             // [e2, 20, 00, 01, 00, 00]
             if _data == [0xe2, 0x20, 0x00, 0x01, 0x00, 0x00] {
                 log::info!("PLL calibration initiated");
                 self.state = SimulatedState::StartingPLLCalibration;
             }
         }
-        log::debug!(
-            "SPI write: {:02x?} (id: {:02x}, sub_id: {:02x}, write: {})",
-            _data,
-            id,
-            sub_id,
-            write
-        );
+
+        match transaction {
+            Ok(transaction @ Transaction::FastCommand(_)) => log::info!("SPI {}", transaction),
+            Ok(transaction) => log::debug!("SPI {}", transaction),
+            Err(error) => log::warn!("SPI write: {:02x?} ({:?})", _data, error),
+        }
         Ok(())
     }
 
@@ -94,14 +93,8 @@ impl hal::spi::SpiBus<u8> for DummySpi {
 
     #[maybe_async_attr]
     async fn transfer_in_place(&mut self, _data: &mut [u8]) -> Result<(), Self::Error> {
-        let (id, sub_id, write) = DummySpi::decode_header(_data);
-        log::debug!(
-            "SPI in-place transfer: {:02x?} (id: {:02x}, sub_id: {:02x}, write: {})",
-            _data,
-            id,
-            sub_id,
-            write
-        );
+        let mosi = _data.to_vec();
+        let address = DummySpi::address(&trace::decode(&mosi, &[], false));
 
         if self.state == SimulatedState::StartingPLLCalibration {
             log::info!("PLL calibration done");
@@ -109,14 +102,14 @@ impl hal::spi::SpiBus<u8> for DummySpi {
         }
 
         if self.state == SimulatedState::PLLCalibrationDone {
-            if id == 0x00 && sub_id == 0x44 {
+            if address == Some((0x00, 0x44)) {
                 // Reading SYS_STATUS register (6 bytes)
                 // Let's return a success
                 _data[2..].copy_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00]);
             }
 
             // rx_cal_sts
-            if id == 0x04 && sub_id == 0x20 {
+            if address == Some((0x04, 0x20)) {
                 // Reading RX_CAL_STS register (1 byte)
                 // Let's return a success
                 _data[2] = 0x01;
@@ -125,6 +118,11 @@ impl hal::spi::SpiBus<u8> for DummySpi {
             }
         }
 
+        match trace::decode(&mosi, _data, false) {
+            Ok(transaction) => log::debug!("SPI {}", transaction),
+            Err(error) => log::warn!("SPI in-place transfer: {:02x?} ({:?})", mosi, error),
+        }
+
         Ok(())
     }
 
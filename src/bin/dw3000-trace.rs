@@ -0,0 +1,233 @@
+//! Annotates a logic analyzer export of DW3000 SPI traffic
+//!
+//! Reads a CSV export of an SPI analyzer, groups the bytes into transactions
+//! and prints each transaction decoded by `dw3000_ng::ll::trace`.
+//!
+//! The header row has to name a `MOSI` and a `MISO` column. Bytes are grouped
+//! into transactions either by a `Packet ID` column (Saleae Logic 1), or by a
+//! `type` column with `enable`, `result` and `disable` rows (Saleae Logic 2).
+//! Without either column, every row is treated as one transaction. Byte
+//! values are hexadecimal, with or without `0x` prefix.
+//!
+//! Usage: `dw3000-trace [--crc] [export.csv]`, reads from stdin without a
+//! file. Pass `--crc` if the DW3000 was in SPI CRC mode during the capture.
+
+use std::{
+    env,
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    process,
+};
+
+use dw3000_ng::ll::trace;
+
+/// A captured transaction
+#[derive(Default)]
+struct Capture {
+    mosi: Vec<u8>,
+    miso: Vec<u8>,
+}
+
+/// Prints the decoded transactions
+struct Printer<W> {
+    output: W,
+    crc: bool,
+    count: usize,
+}
+
+/// The columns of the export that are used
+struct Columns {
+    mosi: usize,
+    miso: usize,
+    packet: Option<usize>,
+    kind: Option<usize>,
+}
+
+impl Columns {
+    fn from_header(header: &[String]) -> Result<Self, String> {
+        let find = |name: &str| header.iter().position(|c| c.eq_ignore_ascii_case(name));
+
+        Ok(Columns {
+            mosi: find("mosi").ok_or("no MOSI column")?,
+            miso: find("miso").ok_or("no MISO column")?,
+            packet: find("packet id"),
+            kind: find("type"),
+        })
+    }
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("dw3000-trace: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut crc = false;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--crc" => crc = true,
+            _ => path = Some(arg),
+        }
+    }
+
+    let input: Box<dyn BufRead> = match path {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+
+    let mut printer = Printer {
+        output: io::stdout().lock(),
+        crc,
+        count: 0,
+    };
+    annotate(input, &mut printer)
+}
+
+/// Groups the rows of a CSV export into transactions and prints them
+fn annotate<W: Write>(input: impl BufRead, printer: &mut Printer<W>) -> Result<(), Box<dyn Error>> {
+    let mut lines = input.lines();
+    let header = split(&lines.next().ok_or("empty input")??);
+    let columns = Columns::from_header(&header)?;
+
+    let mut capture = Capture::default();
+    let mut packet = None;
+
+    for line in lines {
+        let row = split(&line?);
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let cell = |i: usize| row.get(i).map(String::as_str).unwrap_or("");
+
+        if let Some(kind) = columns.kind {
+            match cell(kind) {
+                "enable" => capture = Capture::default(),
+                "disable" => printer.print(&mut capture)?,
+                _ => push(&mut capture, cell(columns.mosi), cell(columns.miso))?,
+            }
+            continue;
+        }
+
+        if let Some(column) = columns.packet {
+            let id = cell(column).to_owned();
+            if packet.as_ref() != Some(&id) {
+                printer.print(&mut capture)?;
+                packet = Some(id);
+            }
+            push(&mut capture, cell(columns.mosi), cell(columns.miso))?;
+            continue;
+        }
+
+        push(&mut capture, cell(columns.mosi), cell(columns.miso))?;
+        printer.print(&mut capture)?;
+    }
+    printer.print(&mut capture)?;
+
+    Ok(())
+}
+
+/// Splits a CSV row into cells, removing quotes and whitespace
+fn split(line: &str) -> Vec<String> {
+    line.split(',')
+        .map(|cell| cell.trim().trim_matches('"').trim().to_owned())
+        .collect()
+}
+
+/// Adds the bytes of a row to the capture
+///
+/// A cell may hold several bytes separated by spaces.
+fn push(capture: &mut Capture, mosi: &str, miso: &str) -> Result<(), String> {
+    for (cell, bytes) in [(mosi, &mut capture.mosi), (miso, &mut capture.miso)] {
+        for byte in cell.split_whitespace() {
+            let digits = byte.trim_start_matches("0x").trim_start_matches("0X");
+            let byte =
+                u8::from_str_radix(digits, 16).map_err(|_| format!("invalid byte: {}", byte))?;
+            bytes.push(byte);
+        }
+    }
+
+    Ok(())
+}
+
+impl<W: Write> Printer<W> {
+    /// Prints and resets the capture, if it isn't empty
+    fn print(&mut self, capture: &mut Capture) -> io::Result<()> {
+        if capture.mosi.is_empty() {
+            return Ok(());
+        }
+
+        match trace::decode(&capture.mosi, &capture.miso, self.crc) {
+            Ok(transaction) => {
+                // Indent the field lines below the transaction
+                let text = transaction.to_string().replace('\n', "\n       ");
+                writeln!(self.output, "{:5}  {}", self.count, text)?;
+            }
+            Err(error) => writeln!(
+                self.output,
+                "{:5}  {:02x?}: {:?}",
+                self.count, capture.mosi, error
+            )?,
+        }
+
+        self.count += 1;
+        *capture = Capture::default();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotate_str(csv: &str, crc: bool) -> String {
+        let mut printer = Printer {
+            output: Vec::new(),
+            crc,
+            count: 0,
+        };
+        annotate(csv.as_bytes(), &mut printer).unwrap();
+
+        String::from_utf8(printer.output).unwrap()
+    }
+
+    #[test]
+    fn packet_id() {
+        let csv = "\
+Time [s],Packet ID,MOSI,MISO
+0.1,0,0x83,0x00
+0.2,1,0x28,0x00
+0.3,1,0x00,0xAB
+0.4,1,0x00,0xCD
+";
+
+        assert_eq!(
+            annotate_str(csv, false),
+            "    0  fast command CMD_TX\n    1  read ? (0x14:0x00) = 0xcdab\n",
+        );
+    }
+
+    #[test]
+    fn enable_result_disable() {
+        let csv = "\
+name,type,start_time,duration,mosi,miso
+SPI,enable,0.1,0,,
+SPI,result,0.1,0,0xA8,0x00
+SPI,result,0.2,0,0xAB,0x00
+SPI,result,0.3,0,0xE8,0x00
+SPI,disable,0.4,0,,
+SPI,enable,0.5,0,,
+SPI,result,0.5,0,0x81,0x00
+SPI,disable,0.6,0,,
+";
+
+        assert_eq!(
+            annotate_str(csv, true),
+            "    0  write ? (0x14:0x00) = 0xab\n    1  fast command CMD_TXRXOFF\n",
+        );
+    }
+}
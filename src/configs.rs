@@ -40,6 +40,31 @@ pub struct Config {
     pub phr_rate: PhrRate,
     /// PDoA mode
     pub pdoa_mode: PdoaMode,
+    /// Clear the event counters before every transmission
+    ///
+    /// Defaults to `true`. Disable this to accumulate statistics with
+    /// [`DW3000::read_event_counters`].
+    ///
+    /// [`DW3000::read_event_counters`]: crate::DW3000::read_event_counters
+    pub clear_event_counters: bool,
+    /// Read the clock offset of every received frame
+    ///
+    /// Defaults to `false`. Enable this to fill in
+    /// [`Message::clock_offset_ppm`], at the cost of one more SPI read per
+    /// frame.
+    ///
+    /// [`Message::clock_offset_ppm`]: crate::hl::Message::clock_offset_ppm
+    pub read_clock_offset: bool,
+    /// Read the RX timestamp relative to the last SYNC pulse of every received
+    /// frame
+    ///
+    /// Defaults to `false`. Enable this together with external receive
+    /// synchronisation (see [`DW3000::set_sync_behaviour`]) to fill in
+    /// [`Message::sync_time`].
+    ///
+    /// [`DW3000::set_sync_behaviour`]: crate::DW3000::set_sync_behaviour
+    /// [`Message::sync_time`]: crate::hl::Message::sync_time
+    pub read_sync_time: bool,
 }
 
 impl Default for Config {
@@ -60,6 +85,9 @@ impl Default for Config {
             phr_mode: Default::default(),
             phr_rate: Default::default(),
             pdoa_mode: Default::default(),
+            clear_event_counters: true,
+            read_clock_offset: false,
+            read_sync_time: false,
         }
     }
 }
@@ -225,6 +253,22 @@ impl UwbChannel {
         }
     }
 
+    /// Gets the center frequency of the channel in Hz
+    pub fn get_center_frequency(&self) -> f32 {
+        match self {
+            UwbChannel::Channel5 => 6489.6e6,
+            UwbChannel::Channel9 => 7987.2e6,
+        }
+    }
+
+    /// Gets the wavelength of the channel's center frequency in meters
+    pub fn get_wavelength(&self) -> f32 {
+        // Speed of light in vacuum (m/s)
+        const SPEED_OF_LIGHT: f32 = 299_792_458.0;
+
+        SPEED_OF_LIGHT / self.get_center_frequency()
+    }
+
     /// Gets the recommended value for rf_tx_ctrl_2
     pub fn get_recommended_rf_tx_ctrl_2(&self) -> u32 {
         match self {
@@ -386,7 +430,7 @@ impl StsLen {
 
         // Compute the square root of the squared value with Newton's method
         let sqrt = |x: u32| -> u32 {
-            let mut z = (x + 1) / 2;
+            let mut z = x.div_ceil(2);
             let mut y = x;
             while z < y {
                 y = z;
@@ -1,7 +1,8 @@
 //! Enumeration for fast commands
 
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, num_enum::TryFromPrimitive)]
+#[repr(u8)]
 /// Fast command enumeration to easily control the module
 pub enum FastCommand {
     /// Fast command to go to IDLE state and clears any events
@@ -32,15 +32,37 @@ where
         Ok(rx_antenna_delay)
     }
 
+    /// Returns the crystal trim
+    #[maybe_async_attr]
+    pub async fn get_xtal_trim(&mut self) -> Result<u8, Error<SPI>> {
+        Ok(self.ll.xtal().read().await?.value() & 0x3F)
+    }
+
     /// Returns the network id and address used for sending and receiving
+    ///
+    /// If no short address is assigned (`0xFFFE` or `0xFFFF`), the extended
+    /// address is returned instead.
     #[maybe_async_attr]
     pub async fn get_address(&mut self) -> Result<(Ieee802154Pan, Ieee802154Address), Error<SPI>> {
         let panadr = self.ll.panadr().read().await?;
 
-        Ok((
-            smoltcp::wire::Ieee802154Pan(panadr.pan_id()),
-            Ieee802154Address::Short(panadr.short_addr().to_be_bytes()),
-        ))
+        let addr = match panadr.short_addr() {
+            0xFFFE | 0xFFFF => self.get_eui().await?,
+            short_addr => Ieee802154Address::Short(short_addr.to_be_bytes()),
+        };
+
+        Ok((smoltcp::wire::Ieee802154Pan(panadr.pan_id()), addr))
+    }
+
+    /// Returns the extended address (EUI-64)
+    ///
+    /// Unless changed with [`DW3000::set_eui`], this is the factory EUI loaded
+    /// from OTP during initialization.
+    #[maybe_async_attr]
+    pub async fn get_eui(&mut self) -> Result<Ieee802154Address, Error<SPI>> {
+        let eui = self.ll.eui().read().await?.value();
+
+        Ok(Ieee802154Address::Extended(eui.to_be_bytes()))
     }
 
     /// Returns the current system time (32-bit)
@@ -81,6 +103,50 @@ where
         Ok(self.ll.sys_status().read().await?.cplock() == 0x1)
     }
 
+    /// Read the OTP memory at the given address
+    #[maybe_async_attr]
+    pub async fn read_otp(&mut self, addr: u16) -> Result<u32, ll::Error<SPI>> {
+        // Set OTP_MAN to 1
+        self.ll.otp_cfg().write(|w| w.otp_man(1)).await?;
+        // Set the 10-bit address
+        self.ll.otp_addr().modify(|_, w| w.otp_addr(addr)).await?;
+        // Set OTP_READ to 1
+        self.ll.otp_cfg().write(|w| w.otp_read(1)).await?;
+        // Read the data (32 bits)
+        let data = self.ll.otp_rdata().read().await?.value();
+        Ok(data)
+    }
+
+    /// Enables SPI CRC mode
+    ///
+    /// From now on, the DW3000 drops register writes with a bad CRC and
+    /// reports them through [`Events::SPICRCE`] and
+    /// [`EventCounters::spi_write_crc_errors`], and register reads are
+    /// checked against `SPI_RD_CRC`. A failed read check is returned as
+    /// [`ll::Error::Crc`]. This costs an extra byte per write and an extra
+    /// transfer per read.
+    ///
+    /// The setting is lost when the DW3000 is reset.
+    ///
+    /// [`Events::SPICRCE`]: super::Events::SPICRCE
+    /// [`EventCounters::spi_write_crc_errors`]: super::EventCounters::spi_write_crc_errors
+    #[maybe_async_attr]
+    pub async fn enable_spi_crc(&mut self) -> Result<(), Error<SPI>> {
+        self.ll.sys_cfg().modify_masked(|w| w.spi_crcen(1)).await?;
+        self.ll.set_spi_crc(true);
+
+        Ok(())
+    }
+
+    /// Disables SPI CRC mode
+    #[maybe_async_attr]
+    pub async fn disable_spi_crc(&mut self) -> Result<(), Error<SPI>> {
+        self.ll.sys_cfg().modify_masked(|w| w.spi_crcen(0)).await?;
+        self.ll.set_spi_crc(false);
+
+        Ok(())
+    }
+
     /// Provides direct access to the register-level API
     ///
     /// Be aware that by using the register-level API, you can invalidate
@@ -144,4 +210,78 @@ mod test {
 
         spi.done();
     }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_get_address_without_short_address() {
+        let spi = SpiMock::new(&[
+            // PANADR without a short address
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x40, 0x30, 0, 0, 0, 0],
+                vec![0, 0, 0xFE, 0xFF, 0x05, 0x06],
+            ),
+            SpiTransaction::transaction_end(),
+            // EUI
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x40, 0x10, 0, 0, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000::new(spi);
+
+        let addr = dw3000.get_address().await.unwrap();
+
+        assert_eq!(
+            addr,
+            (
+                Ieee802154Pan(0x0605),
+                Ieee802154Address::Extended([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08])
+            )
+        );
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_spi_crc() {
+        let spi = SpiMock::new(&[
+            // Enable, the write is sent without a CRC
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0x41, 0xFF, 0x40]),
+            SpiTransaction::transaction_end(),
+            // The read is checked against SPI_RD_CRC
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x40, 0x70, 0, 0, 0, 0],
+                vec![0, 0, 0x78, 0x56, 0x34, 0x12],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x40, 0x60, 0], vec![0, 0, 0xE0]),
+            SpiTransaction::transaction_end(),
+            // Disable, the write still carries a CRC
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0x41, 0xBF, 0x00]),
+            SpiTransaction::write_vec(vec![0xCB]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000::new(spi);
+
+        dw3000.enable_spi_crc().await.unwrap();
+        assert!(dw3000.ll.spi_crc());
+        let sys_time = dw3000.sys_time().await.unwrap();
+        assert_eq!(sys_time, 0x12345678);
+        dw3000.disable_spi_crc().await.unwrap();
+        assert!(!dw3000.ll.spi_crc());
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
 }
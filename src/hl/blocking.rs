@@ -0,0 +1,316 @@
+//! Blocking send and receive helpers with a deadline
+
+use core::time::Duration;
+
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+
+use super::{Message, ReceiveTime, SendTime, RX_WAIT_EVENTS};
+use crate::{
+    maybe_async_attr, spi_type, time::Instant, Config, Error, Ready, Sending,
+    SingleBufferReceiving, DW3000,
+};
+
+/// Interval at which the blocking helpers poll the DW3000, in µs
+const POLL_INTERVAL_US: u32 = 100;
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Sends `data` and waits for the transmission to finish
+    ///
+    /// Works like [`DW3000::send`] followed by `s_wait` and
+    /// `finish_sending`. If the frame hasn't been sent after `timeout`, the
+    /// transmission is aborted and [`Error::Timeout`] is returned. The radio
+    /// is always returned in the `Ready` state, together with the TX
+    /// timestamp or the error.
+    ///
+    /// `timeout` only counts the time spent in `delay`, so the actual
+    /// deadline is slightly later.
+    #[maybe_async_attr]
+    pub async fn send_blocking<DELAY>(
+        mut self,
+        data: &[u8],
+        send_time: SendTime,
+        config: Config,
+        timeout: Duration,
+        mut delay: DELAY,
+    ) -> (DW3000<SPI, Ready>, Result<Instant, Error<SPI>>)
+    where
+        DELAY: DelayNs,
+    {
+        if let Err(error) = self.start_send(data, send_time, &config).await {
+            return (self, Err(error));
+        }
+
+        let mut sending = DW3000 {
+            ll: self.ll,
+            seq: self.seq,
+            state: Sending { finished: false },
+        };
+
+        let mut remaining = timeout.as_micros();
+        let result = loop {
+            match sending.s_wait().await {
+                Ok(instant) => break Ok(instant),
+                Err(nb::Error::WouldBlock) => (),
+                Err(nb::Error::Other(error)) => break Err(error),
+            }
+
+            if remaining == 0 {
+                break Err(Error::Timeout);
+            }
+            delay.delay_us(POLL_INTERVAL_US).await;
+            remaining = remaining.saturating_sub(POLL_INTERVAL_US as u128);
+        };
+
+        let (ready, idle) = match sending.finish_sending().await {
+            Ok(ready) => (ready, Ok(())),
+            Err((sending, error)) => (
+                DW3000 {
+                    ll: sending.ll,
+                    seq: sending.seq,
+                    state: Ready,
+                },
+                Err(error),
+            ),
+        };
+
+        match (result, idle) {
+            (Ok(instant), Ok(())) => (ready, Ok(instant)),
+            (Err(error), _) | (Ok(_), Err(error)) => (ready, Err(error)),
+        }
+    }
+
+    /// Receives a single frame into `buffer`
+    ///
+    /// Works like [`DW3000::receive`] followed by `r_wait` and
+    /// `finish_receiving`. If no frame has been received after `timeout`, the
+    /// receiver is turned off and [`Error::Timeout`] is returned. The radio
+    /// is always returned in the `Ready` state, together with the message or
+    /// the error.
+    ///
+    /// `timeout` only counts the time spent in `delay`, so the actual
+    /// deadline is slightly later.
+    #[maybe_async_attr]
+    pub async fn receive_blocking<'b, DELAY>(
+        self,
+        config: Config,
+        buffer: &'b mut [u8],
+        timeout: Duration,
+        mut delay: DELAY,
+    ) -> (DW3000<SPI, Ready>, Result<Message<'b>, Error<SPI>>)
+    where
+        DELAY: DelayNs,
+    {
+        let mut receiving = DW3000 {
+            ll: self.ll,
+            seq: self.seq,
+            state: SingleBufferReceiving {
+                finished: false,
+                config,
+                sniff: false,
+            },
+        };
+
+        let mut remaining = timeout.as_micros();
+        let result = match receiving.start_receiving(ReceiveTime::Now, config).await {
+            Ok(()) => loop {
+                // `r_wait` borrows the buffer for the lifetime of the message,
+                // so only call it once a frame or an error is pending
+                match receiving.read_events().await {
+                    Ok(events) if events.intersects(RX_WAIT_EVENTS) => {
+                        break match receiving.r_wait(buffer).await {
+                            Ok(message) => Ok(message),
+                            Err(nb::Error::WouldBlock) => Err(Error::RxNotFinished),
+                            Err(nb::Error::Other(error)) => {
+                                // `r_wait` leaves errors pending
+                                receiving.clear_events(RX_WAIT_EVENTS).await.and(Err(error))
+                            }
+                        };
+                    }
+                    Ok(_) => (),
+                    Err(error) => break Err(error),
+                }
+
+                if remaining == 0 {
+                    break Err(Error::Timeout);
+                }
+                delay.delay_us(POLL_INTERVAL_US).await;
+                remaining = remaining.saturating_sub(POLL_INTERVAL_US as u128);
+            },
+            Err(error) => Err(error),
+        };
+
+        let (ready, idle) = match receiving.finish_receiving().await {
+            Ok(ready) => (ready, Ok(())),
+            Err((receiving, error)) => (
+                DW3000 {
+                    ll: receiving.ll,
+                    seq: receiving.seq,
+                    state: Ready,
+                },
+                Err(error),
+            ),
+        };
+
+        match (result, idle) {
+            (Ok(message), Ok(())) => (ready, Ok(message)),
+            (Err(error), _) | (Ok(_), Err(error)) => (ready, Err(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::{
+        delay::{CheckedDelay, Transaction as DelayTransaction},
+        spi::{Mock as SpiMock, Transaction as SpiTransaction},
+    };
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_send_blocking_timeout() {
+        let mut frame = vec![
+            0xE8, 0x00, 0x41, 0x98, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x01, 0x02,
+        ];
+        frame.resize(2 + 127, 0);
+
+        let spi = SpiMock::new(&[
+            // Event counters
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x5E, 0x00, 0], vec![0, 0, 0]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x00, 0x01]),
+            SpiTransaction::transaction_end(),
+            // TX clock
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x11, 0xEF, 0x20]),
+            SpiTransaction::transaction_end(),
+            // PANADR, TX_BUFFER, TX_FCTRL, CMD_TX
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x40, 0x30, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(frame),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x40, 0x90, 0, 0, 0, 0, 0, 0], vec![0; 8]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0x90, 0x0F, 0x50, 0, 0, 0, 0]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x83]),
+            SpiTransaction::transaction_end(),
+            // The frame isn't sent before and after the delay
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x41, 0x10, 0, 0, 0, 0, 0, 0], vec![0; 8]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x41, 0x10, 0, 0, 0, 0, 0, 0], vec![0; 8]),
+            SpiTransaction::transaction_end(),
+            // The transmission is aborted and the TX events are cleared
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x81]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0xF0, 0, 0, 0x08, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_us(POLL_INTERVAL_US)]);
+
+        let dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let (dw3000, result) = dw3000
+            .send_blocking(
+                &[0x01, 0x02],
+                SendTime::Now,
+                Config::default(),
+                Duration::from_micros(POLL_INTERVAL_US as u64),
+                delay.clone(),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        delay.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_receive_blocking_error() {
+        let spi = SpiMock::new(&[
+            // Frame filtering off, CMD_RX
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0x41, 0xFE, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x85]),
+            SpiTransaction::transaction_end(),
+            // Nothing pending before the delay
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x41, 0x10, 0, 0, 0, 0, 0, 0], vec![0; 8]),
+            SpiTransaction::transaction_end(),
+            // RXFCE is pending after the delay, which `r_wait` reports
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x80, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x80, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            // The RX events are cleared
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0x00, 0xD0, 0x33, 0x24, 0, 0]),
+            SpiTransaction::transaction_end(),
+            // finish_receiving turns the receiver off
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x81]),
+            SpiTransaction::transaction_end(),
+        ]);
+        let mut delay = CheckedDelay::new(&[DelayTransaction::delay_us(POLL_INTERVAL_US)]);
+
+        let dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let mut buffer = [0; 128];
+        let (dw3000, result) = dw3000
+            .receive_blocking(
+                Config::default(),
+                &mut buffer,
+                Duration::from_millis(1),
+                delay.clone(),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::Fcs)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        delay.done();
+    }
+}
@@ -0,0 +1,336 @@
+//! Clock offset estimation between the local and the remote crystal
+
+#[cfg(feature = "rssi")]
+use num_traits::Float;
+
+use crate::{configs::UwbChannel, maybe_async_attr, spi_type, Error, DW3000};
+
+use super::Receiving;
+
+/// Converts the carrier recovery integrator value to a frequency offset in Hz
+///
+/// The DW3000 uses the same multiplier for the 16 MHz and the 64 MHz PRF,
+/// unlike the DW1000 where it depended on the PRF.
+const FREQ_OFFSET_MULTIPLIER: f32 = 998.4e6 / 2.0 / 1024.0 / 131072.0;
+
+/// The unit of the `CIA_DIAG_0` clock offset estimate, 2^-26
+const CIA_CLOCK_OFFSET_UNIT: f32 = 1.0 / (1u32 << 26) as f32;
+
+/// The register the clock offset is computed from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockOffsetSource {
+    /// The carrier recovery integrator (`DRX_CAR_INT`)
+    ///
+    /// Measured during the preamble, available for every received frame.
+    #[default]
+    CarrierIntegrator,
+    /// The clock offset estimate of the CIA (`CIA_DIAG_0`)
+    ///
+    /// Computed by the channel impulse response analyser, only available if
+    /// the CIA ran on the received frame.
+    Cia,
+}
+
+/// Converts the 21-bit signed value of `DRX_CAR_INT` to ppm
+///
+/// Follows the convention of `dwt_readclockoffset` in Qorvo's API: a positive
+/// value means that the local clock runs faster than the remote one. The
+/// integrator itself has the opposite sign, hence the negative Hz to ppm
+/// multiplier (`HERTZ_TO_PPM_MULTIPLIER_CHAN_5` in the examples).
+pub(crate) fn carrier_integrator_to_ppm(raw: u32, channel: UwbChannel) -> f32 {
+    // The integrator is a 21-bit two's complement number
+    let value = ((raw << 11) as i32) >> 11;
+
+    let offset_hz = value as f32 * FREQ_OFFSET_MULTIPLIER;
+
+    offset_hz * -1.0e6 / channel.get_center_frequency()
+}
+
+/// Converts the 13-bit signed clock offset estimate of `CIA_DIAG_0` to ppm
+///
+/// Uses the same sign convention as [`carrier_integrator_to_ppm`].
+pub(crate) fn cia_clock_offset_to_ppm(raw: u16) -> f32 {
+    // The estimate is a 13-bit two's complement number
+    let value = ((raw << 3) as i16) >> 3;
+
+    value as f32 * CIA_CLOCK_OFFSET_UNIT * 1.0e6
+}
+
+/// The highest valid crystal trim value
+#[cfg(feature = "rssi")]
+const XTAL_TRIM_MAX: u8 = 0x3F;
+
+/// Closed-loop crystal trim calibration against a reference node
+///
+/// Feed the calibrator with clock offsets measured against a reference node
+/// (see [`DW3000::clock_offset_ppm`]). Once enough samples are collected, it
+/// averages them and returns the next trim value to try, which has to be
+/// applied with [`DW3000::set_xtal_trim`] before more offsets are measured.
+/// When the offset is within the tolerance, or can't be improved any
+/// further, the calibrator has converged and [`XtalTrimCalibrator::trim`]
+/// returns the value to persist.
+///
+/// Requires the `rssi` feature for its float math.
+///
+/// A positive offset, a local clock that is too fast, raises the trim value.
+/// This adds load capacitance to the crystal and lowers its frequency.
+///
+/// ``` rust
+/// use dw3000_ng::hl::XtalTrimCalibrator;
+///
+/// let mut calibrator = XtalTrimCalibrator::new(0x2E);
+///
+/// // Simulated crystal with its nominal frequency at a trim of 30
+/// let offset = |trim: u8| (30.0 - trim as f32) * 1.2;
+///
+/// while !calibrator.is_converged() {
+///     let measured = offset(calibrator.trim());
+///     if let Some(trim) = calibrator.update(measured) {
+///         // dw3000.set_xtal_trim(trim)?;
+///     }
+/// }
+///
+/// assert_eq!(calibrator.trim(), 30);
+/// ```
+#[cfg(feature = "rssi")]
+#[derive(Clone, Copy, Debug)]
+pub struct XtalTrimCalibrator {
+    trim: u8,
+    tolerance_ppm: f32,
+    samples_per_step: u8,
+    ppm_per_step: f32,
+    sum: f32,
+    count: u8,
+    previous: Option<(u8, f32)>,
+    converged: bool,
+}
+
+#[cfg(feature = "rssi")]
+impl XtalTrimCalibrator {
+    /// Creates a new calibrator starting at the given trim value
+    ///
+    /// Defaults to a tolerance of ±1 ppm and 8 samples per step.
+    pub fn new(trim: u8) -> Self {
+        XtalTrimCalibrator {
+            trim: trim.min(XTAL_TRIM_MAX),
+            tolerance_ppm: 1.0,
+            samples_per_step: 8,
+            // Nominal estimate, refined from the measurements after each step
+            ppm_per_step: 1.0,
+            sum: 0.0,
+            count: 0,
+            previous: None,
+            converged: false,
+        }
+    }
+
+    /// Sets the tolerated clock offset in ppm
+    pub fn with_tolerance(mut self, tolerance_ppm: f32) -> Self {
+        self.tolerance_ppm = tolerance_ppm;
+        self
+    }
+
+    /// Sets the number of offsets averaged before the trim is changed
+    pub fn with_samples_per_step(mut self, samples: u8) -> Self {
+        self.samples_per_step = samples.max(1);
+        self
+    }
+
+    /// Returns the current trim value
+    pub fn trim(&self) -> u8 {
+        self.trim
+    }
+
+    /// Returns `true` once the calibration has finished
+    pub fn is_converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Adds a clock offset measured with the current trim value
+    ///
+    /// Returns the new trim value if it changed.
+    pub fn update(&mut self, offset_ppm: f32) -> Option<u8> {
+        if self.converged {
+            return None;
+        }
+
+        self.sum += offset_ppm;
+        self.count += 1;
+        if self.count < self.samples_per_step {
+            return None;
+        }
+
+        let average = self.sum / self.count as f32;
+        self.sum = 0.0;
+        self.count = 0;
+
+        if Float::abs(average) <= self.tolerance_ppm {
+            self.converged = true;
+            return None;
+        }
+
+        if let Some((previous_trim, previous_average)) = self.previous {
+            let steps = self.trim as f32 - previous_trim as f32;
+
+            // Oscillating between two neighbouring values, keep the better one
+            if Float::abs(steps) <= 1.0 && (previous_average > 0.0) != (average > 0.0) {
+                self.converged = true;
+                if Float::abs(previous_average) < Float::abs(average) {
+                    self.trim = previous_trim;
+                    return Some(self.trim);
+                }
+                return None;
+            }
+
+            // Refine the sensitivity of the crystal, a higher trim value
+            // lowers the offset
+            let ppm_per_step = (previous_average - average) / steps;
+            if ppm_per_step > 0.0 {
+                self.ppm_per_step = ppm_per_step;
+            }
+        }
+
+        // A positive offset means the local clock is too fast, which requires
+        // a higher trim value. Round to the nearest step, but move at least one.
+        let steps = average / self.ppm_per_step;
+        let steps = ((steps + if steps > 0.0 { 0.5 } else { -0.5 }) as i16).clamp(-64, 64);
+        let steps = match steps {
+            0 if average > 0.0 => 1,
+            0 => -1,
+            steps => steps,
+        };
+
+        let trim = (self.trim as i16 + steps).clamp(0, XTAL_TRIM_MAX as i16) as u8;
+        if trim == self.trim {
+            // The limit of the trim range has been reached
+            self.converged = true;
+            return None;
+        }
+
+        self.previous = Some((self.trim, average));
+        self.trim = trim;
+
+        Some(trim)
+    }
+}
+
+impl<SPI, RECEIVING> DW3000<SPI, RECEIVING>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    RECEIVING: Receiving,
+{
+    /// Returns the clock offset between the local and the remote crystal of
+    /// the last received frame in ppm
+    ///
+    /// A positive value means that the local clock runs faster than the remote
+    /// one, as with `dwt_readclockoffset`. In single-sided two-way ranging,
+    /// the initiator can correct the reply time reported by the responder with
+    /// the offset measured on the response:
+    ///
+    /// `tof = (round_trip - reply_time * (1 - ppm * 1e-6)) / 2`
+    #[maybe_async_attr]
+    pub async fn clock_offset_ppm(&mut self, source: ClockOffsetSource) -> Result<f32, Error<SPI>> {
+        let ppm = match source {
+            ClockOffsetSource::CarrierIntegrator => {
+                let channel = self.state.get_rx_config().channel;
+                let raw = self.ll.drx_car_int().read().await?.value();
+
+                carrier_integrator_to_ppm(raw, channel)
+            }
+            ClockOffsetSource::Cia => {
+                let raw = self.ll.cia_diag_0().read().await?.coe_ppm();
+
+                cia_clock_offset_to_ppm(raw)
+            }
+        };
+
+        Ok(ppm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carrier_integrator_conversion() {
+        assert_eq!(carrier_integrator_to_ppm(0, UwbChannel::Channel5), 0.0);
+
+        // -1000 * 3.7193 Hz at 6489.6 MHz, the local clock is faster
+        let ppm = carrier_integrator_to_ppm(0x1F_FC18, UwbChannel::Channel5);
+        assert!((ppm - 0.573123).abs() < 1e-5);
+
+        // 1000 * 3.7193 Hz at 7987.2 MHz, the local clock is slower
+        let ppm = carrier_integrator_to_ppm(1000, UwbChannel::Channel9);
+        assert!((ppm + 0.465661).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cia_clock_offset_conversion() {
+        // 1 ppm is roughly 67 units of 2^-26
+        assert!((cia_clock_offset_to_ppm(67) - 0.998).abs() < 1e-3);
+        // -67 as a 13-bit two's complement number
+        assert!((cia_clock_offset_to_ppm(0x1FBD) + 0.998).abs() < 1e-3);
+    }
+
+    #[cfg(feature = "rssi")]
+    fn calibrate(calibrator: &mut XtalTrimCalibrator, offset: impl Fn(u8) -> f32) {
+        let mut updates = 0;
+        while !calibrator.is_converged() {
+            if calibrator.update(offset(calibrator.trim())).is_some() {
+                updates += 1;
+            }
+            assert!(updates < 16);
+        }
+    }
+
+    #[cfg(feature = "rssi")]
+    #[test]
+    fn xtal_trim_direction() {
+        // Local clock too fast, the trim has to go up
+        let mut calibrator = XtalTrimCalibrator::new(0x20).with_samples_per_step(1);
+        assert_eq!(calibrator.update(3.0), Some(0x23));
+
+        // Local clock too slow, the trim has to go down
+        let mut calibrator = XtalTrimCalibrator::new(0x20).with_samples_per_step(1);
+        assert_eq!(calibrator.update(-3.0), Some(0x1D));
+
+        // Within the tolerance, the trim is kept
+        let mut calibrator = XtalTrimCalibrator::new(0x20).with_samples_per_step(1);
+        assert_eq!(calibrator.update(0.5), None);
+        assert!(calibrator.is_converged());
+    }
+
+    #[cfg(feature = "rssi")]
+    #[test]
+    fn xtal_trim_converges_within_tolerance() {
+        let mut calibrator = XtalTrimCalibrator::new(0x2E).with_samples_per_step(1);
+
+        calibrate(&mut calibrator, |trim| (30.0 - trim as f32) * 1.2);
+
+        assert_eq!(calibrator.trim(), 30);
+    }
+
+    #[cfg(feature = "rssi")]
+    #[test]
+    fn xtal_trim_settles_on_best_value() {
+        // The crystal is too sensitive to reach the tolerance
+        let offset = |trim: u8| (30.4 - trim as f32) * 2.5;
+        let mut calibrator = XtalTrimCalibrator::new(0x2E).with_samples_per_step(4);
+
+        calibrate(&mut calibrator, offset);
+
+        assert_eq!(calibrator.trim(), 30);
+    }
+
+    #[cfg(feature = "rssi")]
+    #[test]
+    fn xtal_trim_stops_at_range_limit() {
+        let mut calibrator = XtalTrimCalibrator::new(0x3E).with_samples_per_step(1);
+
+        calibrate(&mut calibrator, |trim| (80.0 - trim as f32) * 1.0);
+
+        assert_eq!(calibrator.trim(), 0x3F);
+    }
+}
@@ -0,0 +1,399 @@
+//! Temperature compensation of the transmitter
+//!
+//! The bandwidth and output power of the DW3000's transmitter drift with
+//! temperature. The pulse generator delay (`PG_DELAY`) is recalibrated with
+//! the pulse generator counter, which measures the current pulse width. This
+//! follows `dwt_calcpgcount` and `dwt_calcbandwidthadj` of Qorvo's DW3000 API.
+//! The fine gain of `TX_POWER` is scaled with the per-channel temperature
+//! factors of `dwt_calcpowertempadj`.
+
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+
+use super::{Awake, Celsius, Volts};
+use crate::{configs::UwbChannel, maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// `RF_ENABLE` bits that enable the transmitter blocks on channel 5
+const RF_ENABLE_TX_CH5: u32 = 0x0200_3C00;
+/// `RF_ENABLE` bits that enable the transmitter blocks on channel 9
+const RF_ENABLE_TX_CH9: u32 = 0x0200_1C00;
+/// `LDO_CTRL` bits that enable the transmitter LDOs and their references
+const LDO_CTRL_TX: u32 = 0x0B00_0B00;
+
+/// `CLK_CTRL` selection that forces a clock to the PLL
+const CLK_FORCE_PLL: u8 = 0b10;
+
+/// Fine gain steps per SAR temperature unit on channel 5, as Q12
+/// (`TEMP_COMP_FACTOR_CH5` of Qorvo's API, 0.1482 * 4096)
+const TEMP_COMP_FACTOR_CH5: i32 = 607;
+/// Fine gain steps per SAR temperature unit on channel 9, as Q12
+/// (`TEMP_COMP_FACTOR_CH9` of Qorvo's API)
+const TEMP_COMP_FACTOR_CH9: i32 = 607;
+
+/// Temperature change per SAR unit in degrees Celsius
+const SAR_CELSIUS_PER_UNIT: f32 = 1.05;
+
+/// Interval at which `PGC_CTRL` is polled, in µs
+const PGC_POLL_INTERVAL_US: u32 = 10;
+
+/// Time after which a pulse generator calibration is abandoned, in µs
+const PGC_TIMEOUT_US: u32 = 1000;
+
+/// Temperature compensation state for the transmitter
+///
+/// Created by [`DW3000::calibrate_tx_compensation`] at the reference
+/// temperature, then passed to [`DW3000::compensate_tx`] between ranging
+/// rounds.
+#[derive(Clone, Copy, Debug)]
+pub struct TxCompensation {
+    reference_temperature: Celsius,
+    reference_pg_count: u16,
+    reference_tx_power: u32,
+    channel: UwbChannel,
+    power_coefficient: Option<f32>,
+    threshold: f32,
+    last_temperature: Celsius,
+}
+
+impl TxCompensation {
+    /// Overrides the number of fine gain steps added to `TX_POWER` per degree
+    /// Celsius above the reference temperature
+    ///
+    /// By default, the factor of Qorvo's API for the configured channel is
+    /// used. Set this if the output power of your board drifts differently,
+    /// or to 0 to leave `TX_POWER` unchanged.
+    pub fn with_power_coefficient(mut self, steps_per_celsius: f32) -> Self {
+        self.power_coefficient = Some(steps_per_celsius);
+        self
+    }
+
+    /// Sets the temperature change that triggers a new compensation
+    ///
+    /// Defaults to 2 °C.
+    pub fn with_threshold(mut self, celsius: f32) -> Self {
+        self.threshold = celsius;
+        self
+    }
+
+    /// Returns the temperature the compensation was calibrated at
+    pub fn reference_temperature(&self) -> Celsius {
+        self.reference_temperature
+    }
+
+    /// Returns the pulse generator count measured at the reference temperature
+    pub fn reference_pg_count(&self) -> u16 {
+        self.reference_pg_count
+    }
+
+    /// Returns the temperature of the last compensation
+    pub fn last_temperature(&self) -> Celsius {
+        self.last_temperature
+    }
+
+    /// Computes the number of fine gain steps to add at the given temperature
+    fn power_steps(&self, temperature: Celsius) -> i16 {
+        let delta = temperature.0 - self.reference_temperature.0;
+
+        if let Some(coefficient) = self.power_coefficient {
+            let steps = delta * coefficient;
+            return (steps + if steps > 0.0 { 0.5 } else { -0.5 }) as i16;
+        }
+
+        // Like `dwt_calcpowertempadj`, scale the magnitude of the change in
+        // SAR units and truncate, then restore the sign
+        let factor = match self.channel {
+            UwbChannel::Channel5 => TEMP_COMP_FACTOR_CH5,
+            UwbChannel::Channel9 => TEMP_COMP_FACTOR_CH9,
+        };
+        let units = (delta / SAR_CELSIUS_PER_UNIT) as i32;
+        let steps = ((units.abs() * factor) >> 12) as i16;
+
+        if units < 0 {
+            -steps
+        } else {
+            steps
+        }
+    }
+
+    /// Computes the `TX_POWER` value for the given temperature
+    fn tx_power(&self, temperature: Celsius) -> u32 {
+        let steps = self.power_steps(temperature);
+        if steps == 0 {
+            return self.reference_tx_power;
+        }
+
+        // Every byte holds the coarse gain in bits 0-1 and the fine gain in
+        // bits 2-7
+        let mut value = self.reference_tx_power.to_le_bytes();
+        for byte in value.iter_mut() {
+            let fine = ((*byte >> 2) as i16 + steps).clamp(0, 0x3F) as u8;
+            *byte = (fine << 2) | (*byte & 0x03);
+        }
+
+        u32::from_le_bytes(value)
+    }
+}
+
+/// The configuration changed by `enable_tx_blocks`
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SavedTxBlocks {
+    sys_clk: u8,
+    tx_clk: u8,
+    ldo_ctrl: (u16, u16),
+    rf_enable: u32,
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Measures the current pulse generator count
+    ///
+    /// The count is proportional to the pulse width at the current
+    /// temperature. `delay` is used to wait between polls of the pulse
+    /// generator calibration.
+    #[maybe_async_attr]
+    pub async fn pg_count<DELAY>(&mut self, delay: &mut DELAY) -> Result<u16, Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let saved = self.enable_tx_blocks().await?;
+
+        self.ll.pgc_ctrl().modify_masked(|w| w.pg_start(1)).await?;
+        let done = self.wait_for_pgc(delay).await;
+
+        let count = self.ll.pgc_status().read().await?.pg_delay_cnt();
+        self.disable_tx_blocks(saved).await?;
+        done?;
+
+        Ok(count)
+    }
+
+    /// Adjusts `PG_DELAY` until the pulse generator count reaches `target`
+    ///
+    /// Returns the new `PG_DELAY` value. `delay` is used to wait between
+    /// polls of the pulse generator calibration.
+    #[maybe_async_attr]
+    pub async fn adjust_pg_delay<DELAY>(
+        &mut self,
+        target: u16,
+        delay: &mut DELAY,
+    ) -> Result<u8, Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let saved = self.enable_tx_blocks().await?;
+
+        self.ll
+            .pg_cal_target()
+            .write(|w| w.value(target & 0x0FFF))
+            .await?;
+        self.ll
+            .pgc_ctrl()
+            .modify_masked(|w| w.pg_start(1).pgc_auto_cal(1))
+            .await?;
+        let done = self.wait_for_pgc(delay).await;
+
+        self.disable_tx_blocks(saved).await?;
+        done?;
+
+        Ok((self.ll.rf_tx_ctrl_2().read().await?.value() & 0x3F) as u8)
+    }
+
+    /// Captures the reference for the transmitter temperature compensation
+    ///
+    /// Call this once the transmitter has been configured and `TX_POWER` is
+    /// set to the desired value, ideally at room temperature. `delay` is used
+    /// to wait for the temperature measurement and the pulse generator
+    /// calibration.
+    #[maybe_async_attr]
+    pub async fn calibrate_tx_compensation<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<TxCompensation, Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let (temperature, _) = self.read_temperature_and_voltage(delay).await?;
+        let reference_pg_count = self.pg_count(delay).await?;
+        let reference_tx_power = self.ll.tx_power().read().await?.value();
+        let channel = if self.ll.chan_ctrl().read().await?.rf_chan() == 0 {
+            UwbChannel::Channel5
+        } else {
+            UwbChannel::Channel9
+        };
+
+        Ok(TxCompensation {
+            reference_temperature: temperature,
+            reference_pg_count,
+            reference_tx_power,
+            channel,
+            power_coefficient: None,
+            threshold: 2.0,
+            last_temperature: temperature,
+        })
+    }
+
+    /// Compensates the transmitter for the current temperature
+    ///
+    /// Samples the temperature and, if it changed by more than the threshold
+    /// since the last compensation, recalibrates `PG_DELAY` to the reference
+    /// pulse generator count and scales `TX_POWER`. Call this between ranging
+    /// rounds, never while a frame is being sent.
+    ///
+    /// Returns the measured temperature and voltage, and whether the
+    /// transmitter was adjusted.
+    #[maybe_async_attr]
+    pub async fn compensate_tx<DELAY>(
+        &mut self,
+        compensation: &mut TxCompensation,
+        delay: &mut DELAY,
+    ) -> Result<(Celsius, Volts, bool), Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let (temperature, voltage) = self.read_temperature_and_voltage(delay).await?;
+
+        let change = temperature.0 - compensation.last_temperature.0;
+        if change < compensation.threshold && -change < compensation.threshold {
+            return Ok((temperature, voltage, false));
+        }
+
+        self.adjust_pg_delay(compensation.reference_pg_count, delay)
+            .await?;
+        let tx_power = compensation.tx_power(temperature);
+        self.ll.tx_power().write(|w| w.value(tx_power)).await?;
+
+        compensation.last_temperature = temperature;
+
+        Ok((temperature, voltage, true))
+    }
+
+    /// Waits until the pulse generator calibration has finished
+    #[maybe_async_attr]
+    async fn wait_for_pgc<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let mut remaining = PGC_TIMEOUT_US;
+        loop {
+            if self.ll.pgc_ctrl().read().await?.pg_start() == 0 {
+                return Ok(());
+            }
+            if remaining == 0 {
+                return Err(Error::Timeout);
+            }
+            delay.delay_us(PGC_POLL_INTERVAL_US).await;
+            remaining = remaining.saturating_sub(PGC_POLL_INTERVAL_US);
+        }
+    }
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Forces the TX clocks on and enables the transmitter blocks
+    ///
+    /// Returns the previous configuration, to be restored with
+    /// `disable_tx_blocks`.
+    #[maybe_async_attr]
+    pub(super) async fn enable_tx_blocks(&mut self) -> Result<SavedTxBlocks, Error<SPI>> {
+        let clk_ctrl = self.ll.clk_ctrl().read().await?;
+        let ldo_ctrl = self.ll.ldo_ctrl().read().await?;
+        let saved = SavedTxBlocks {
+            sys_clk: clk_ctrl.sys_clk(),
+            tx_clk: clk_ctrl.tx_clk(),
+            ldo_ctrl: (ldo_ctrl.low(), ldo_ctrl.high()),
+            rf_enable: self.ll.rf_enable().read().await?.value(),
+        };
+
+        self.ll
+            .clk_ctrl()
+            .modify_masked(|w| w.sys_clk(CLK_FORCE_PLL).tx_clk(CLK_FORCE_PLL))
+            .await?;
+
+        let rf_enable = if self.ll.chan_ctrl().read().await?.rf_chan() == 0 {
+            RF_ENABLE_TX_CH5
+        } else {
+            RF_ENABLE_TX_CH9
+        };
+        self.ll
+            .ldo_ctrl()
+            .modify(|r, w| {
+                w.low(r.low() | LDO_CTRL_TX as u16)
+                    .high(r.high() | (LDO_CTRL_TX >> 16) as u16)
+            })
+            .await?;
+        self.ll
+            .rf_enable()
+            .modify(|r, w| w.value(r.value() | rf_enable))
+            .await?;
+
+        Ok(saved)
+    }
+
+    /// Restores the transmitter blocks and clocks
+    #[maybe_async_attr]
+    pub(super) async fn disable_tx_blocks(
+        &mut self,
+        saved: SavedTxBlocks,
+    ) -> Result<(), Error<SPI>> {
+        self.ll
+            .rf_enable()
+            .write(|w| w.value(saved.rf_enable))
+            .await?;
+        self.ll
+            .ldo_ctrl()
+            .write(|w| w.low(saved.ldo_ctrl.0).high(saved.ldo_ctrl.1))
+            .await?;
+        self.ll
+            .clk_ctrl()
+            .modify_masked(|w| w.sys_clk(saved.sys_clk).tx_clk(saved.tx_clk))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_power_scaling() {
+        let compensation = TxCompensation {
+            reference_temperature: Celsius(20.0),
+            reference_pg_count: 0,
+            reference_tx_power: 0x7C_7C_7C_7C,
+            channel: UwbChannel::Channel5,
+            power_coefficient: None,
+            threshold: 2.0,
+            last_temperature: Celsius(20.0),
+        };
+
+        assert_eq!(compensation.tx_power(Celsius(20.0)), 0x7C_7C_7C_7C);
+        // 6 SAR units are less than a step
+        assert_eq!(compensation.tx_power(Celsius(27.0)), 0x7C_7C_7C_7C);
+        // 38 SAR units at 60 °C, 5 steps more
+        assert_eq!(compensation.tx_power(Celsius(60.0)), 0x90_90_90_90);
+        // 38 SAR units at -20 °C, 5 steps less
+        assert_eq!(compensation.tx_power(Celsius(-20.0)), 0x68_68_68_68);
+
+        // The fine gain saturates, the coarse gain is kept
+        let compensation = TxCompensation {
+            reference_tx_power: 0xFE_FE_FE_FE,
+            ..compensation
+        };
+        assert_eq!(compensation.tx_power(Celsius(60.0)), 0xFE_FE_FE_FE);
+
+        // A board specific coefficient overrides the channel factor
+        let compensation = compensation.with_power_coefficient(0.2);
+        assert_eq!(compensation.tx_power(Celsius(-20.0)), 0xDE_DE_DE_DE);
+        let compensation = compensation.with_power_coefficient(0.0);
+        assert_eq!(compensation.tx_power(Celsius(-20.0)), 0xFE_FE_FE_FE);
+    }
+}
@@ -0,0 +1,205 @@
+//! Support for sharing the DW3000 between two SPI hosts
+//!
+//! The DW3000 has two SPI ports. If both hosts access it at the same time,
+//! the DW3000 flags a collision in `SYS_STATUS` and records the details in
+//! `SPI_COLLISION`. To take turns, a host acquires the SPI semaphore with
+//! [`DW3000::acquire_spi_semaphore`] before a sequence of accesses, and
+//! releases it with [`DW3000::release_spi_semaphore`] afterwards. Use
+//! [`DW3000::check_spi_collision`] to detect accesses that collided anyway.
+//!
+//! The semaphore register isn't described in the public user manual. Its
+//! layout follows the `dwt_ds_sema_*` functions of Qorvo's DW3xxx API, and
+//! hasn't been verified on hardware.
+
+use super::{Awake, Events};
+use crate::{maybe_async_attr, spi_type, spi_type::spi::Mode, Error, DW3000};
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+/// The SPI ports of the DW3000
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub enum SpiPort {
+    /// The main SPI port
+    Spi1,
+    /// The second SPI port, on the GPIO pins
+    Spi2,
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Returns the SPI mode the DW3000 was strapped to at reset
+    #[maybe_async_attr]
+    pub async fn spi_mode(&mut self) -> Result<Mode, Error<SPI>> {
+        let mode = match self.ll.spi_mode().read().await?.value() {
+            0 => spi_type::spi::MODE_0,
+            1 => spi_type::spi::MODE_1,
+            2 => spi_type::spi::MODE_2,
+            _ => spi_type::spi::MODE_3,
+        };
+
+        Ok(mode)
+    }
+
+    /// Checks whether an SPI collision happened
+    ///
+    /// If the DW3000 flagged a collision since the last check, the collision
+    /// status and the [`Events::SPIERR`] event are cleared and
+    /// [`Error::SpiCollision`] is returned. Any access of this host since the
+    /// last check may have been corrupted and should be repeated.
+    #[maybe_async_attr]
+    pub async fn check_spi_collision(&mut self) -> Result<(), Error<SPI>> {
+        if self.ll.sys_status().read().await?.spierr() == 0 {
+            return Ok(());
+        }
+
+        let status = self.ll.spi_collision().read().await?.value();
+        self.ll.spi_collision().write(|w| w.value(status)).await?;
+        self.clear_events(Events::SPIERR).await?;
+
+        Err(Error::SpiCollision { status })
+    }
+
+    /// Requests the SPI semaphore for `port`, the port this host is
+    /// connected to
+    ///
+    /// Returns [`Error::SpiSemaphoreRefused`] if the other host holds the
+    /// semaphore, or [`Error::SpiCollision`] if the request collided with an
+    /// access of the other host. In both cases, try again later.
+    #[maybe_async_attr]
+    pub async fn acquire_spi_semaphore(&mut self, port: SpiPort) -> Result<(), Error<SPI>> {
+        self.ll.spi_sem().modify_masked(|w| w.req(1)).await?;
+        self.check_spi_collision().await?;
+
+        let status = self.ll.spi_sem().read().await?;
+        let granted = match port {
+            SpiPort::Spi1 => status.spi1(),
+            SpiPort::Spi2 => status.spi2(),
+        };
+        if granted == 0 {
+            return Err(Error::SpiSemaphoreRefused);
+        }
+
+        Ok(())
+    }
+
+    /// Releases the SPI semaphore, so the other host can acquire it
+    #[maybe_async_attr]
+    pub async fn release_spi_semaphore(&mut self) -> Result<(), Error<SPI>> {
+        self.ll.spi_sem().modify_masked(|w| w.rel(1)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ll, Ready};
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_check_spi_collision() {
+        let spi = SpiMock::new(&[
+            // SYS_STATUS with SPIERR set
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x80, 0x00, 0x00, 0x08],
+            ),
+            SpiTransaction::transaction_end(),
+            // SPI_COLLISION, read and cleared
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x42, 0x80, 0], vec![0, 0, 0x05]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC2, 0x80, 0x05]),
+            SpiTransaction::transaction_end(),
+            // SPIERR cleared
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0, 0, 0, 0, 0, 0x08]),
+            SpiTransaction::transaction_end(),
+            // SYS_STATUS without SPIERR
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let result = dw3000.check_spi_collision().await;
+        assert!(matches!(result, Err(Error::SpiCollision { status: 0x05 })));
+        let result = dw3000.check_spi_collision().await;
+        assert!(result.is_ok());
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_spi_semaphore() {
+        let spi = SpiMock::new(&[
+            // Request, granted to SPI1
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x91, 0xFF, 0x01]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x62, 0x90, 0, 0], vec![0, 0, 0x00, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Release
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x91, 0xFF, 0x02]),
+            SpiTransaction::transaction_end(),
+            // Request, refused because SPI2 holds the semaphore
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x91, 0xFF, 0x01]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x62, 0x90, 0, 0], vec![0, 0, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let result = dw3000.acquire_spi_semaphore(SpiPort::Spi1).await;
+        assert!(result.is_ok());
+        let result = dw3000.release_spi_semaphore().await;
+        assert!(result.is_ok());
+        let result = dw3000.acquire_spi_semaphore(SpiPort::Spi1).await;
+        assert!(matches!(result, Err(Error::SpiSemaphoreRefused)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
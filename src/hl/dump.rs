@@ -0,0 +1,233 @@
+//! Register dumps for debugging
+
+#[cfg(feature = "std")]
+use std::{fmt, vec::Vec};
+
+use super::Awake;
+use crate::{
+    ll::{RegisterInfo, REGISTERS},
+    maybe_async_attr, spi_type, Error, DW3000,
+};
+
+/// Registers longer than this are skipped by [`DW3000::dump_registers`]
+///
+/// This skips the accumulator memory and the double buffer diagnostics.
+pub const DUMP_MAX_LEN: usize = 128;
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Reads every register of the register map
+    ///
+    /// Calls `f` with the description and the content of each register, least
+    /// significant byte first. Registers longer than [`DUMP_MAX_LEN`] are
+    /// skipped. Use [`RegisterDump`] to collect and print the registers
+    /// (requires the `std` feature). Note that [`RegisterInfo::reset`] is only
+    /// declared for a few registers.
+    #[maybe_async_attr]
+    pub async fn dump_registers<F>(&mut self, f: &mut F) -> Result<(), Error<SPI>>
+    where
+        F: FnMut(RegisterInfo, &[u8]),
+    {
+        let mut buffer = [0; DUMP_MAX_LEN];
+
+        for register in REGISTERS.iter().filter(|r| r.len <= DUMP_MAX_LEN) {
+            let data = &mut buffer[..register.len];
+            self.ll.read_register(register, data).await?;
+            f(*register, data);
+        }
+
+        Ok(())
+    }
+}
+
+/// A snapshot of the registers, as read by [`DW3000::dump_registers`]
+///
+/// The [`Display`] implementation prints every register and its decoded
+/// fields. Registers that differ from the reset value declared in the
+/// register map are marked with `*`, and so are their differing fields,
+/// followed by the reset value. The register map only declares the reset
+/// value of 19 registers so far, so the other registers are never marked,
+/// even if they have been changed. To find out why one board misbehaves, take
+/// a dump of a working board as the baseline, and print
+/// [`RegisterDump::diff`].
+///
+/// [`Display`]: fmt::Display
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegisterDump {
+    registers: Vec<(RegisterInfo, Vec<u8>)>,
+}
+
+#[cfg(feature = "std")]
+impl RegisterDump {
+    /// Creates an empty dump
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads all registers into a new dump
+    #[maybe_async_attr]
+    pub async fn read<SPI, State>(dw3000: &mut DW3000<SPI, State>) -> Result<Self, Error<SPI>>
+    where
+        SPI: spi_type::spi::SpiDevice<u8>,
+        State: Awake,
+    {
+        let mut dump = Self::new();
+        dw3000
+            .dump_registers(&mut |register, data| dump.push(register, data))
+            .await?;
+
+        Ok(dump)
+    }
+
+    /// Adds a register to the dump
+    pub fn push(&mut self, register: RegisterInfo, data: &[u8]) {
+        self.registers.push((register, data.to_vec()));
+    }
+
+    /// Returns the content of the register with the given name
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.registers
+            .iter()
+            .find(|(register, _)| register.name == name)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Returns a printable comparison against `baseline`
+    ///
+    /// Only registers that differ are printed. Fields whose value differs
+    /// from the baseline are marked with `*`, followed by the baseline value.
+    pub fn diff<'a>(&'a self, baseline: &'a RegisterDump) -> RegisterDiff<'a> {
+        RegisterDiff {
+            dump: self,
+            baseline,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for RegisterDump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (register, data) in &self.registers {
+            let reset = register.reset_data();
+            let reset = reset
+                .as_ref()
+                .map(|reset| &reset[..data.len().min(reset.len())]);
+
+            if reset.is_some_and(|reset| reset != data.as_slice()) {
+                write!(f, "* ")?;
+            }
+            write_register(f, register, data, reset.map(|reset| ("reset", reset)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Comparison of two [`RegisterDump`]s, see [`RegisterDump::diff`]
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterDiff<'a> {
+    dump: &'a RegisterDump,
+    baseline: &'a RegisterDump,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for RegisterDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (register, data) in &self.dump.registers {
+            let baseline = self.baseline.get(register.name);
+            if baseline != Some(data.as_slice()) {
+                let baseline = baseline.unwrap_or(&[]);
+                write_register(f, register, data, Some(("was", baseline)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints a register and its fields, marking fields that differ from
+/// `reference`, which is printed with its label
+#[cfg(feature = "std")]
+fn write_register(
+    f: &mut fmt::Formatter,
+    register: &RegisterInfo,
+    data: &[u8],
+    reference: Option<(&str, &[u8])>,
+) -> fmt::Result {
+    write!(
+        f,
+        "{} (0x{:02X}:0x{:02X}) = 0x",
+        register.name, register.id, register.sub_id
+    )?;
+    for byte in data.iter().rev() {
+        write!(f, "{:02x}", byte)?;
+    }
+    writeln!(f)?;
+
+    for field in register.fields {
+        let value = field.value(data);
+        let old = reference.map(|(label, reference)| (label, field.value(reference)));
+
+        match old {
+            Some((label, old)) if old != value => writeln!(
+                f,
+                "  * {} = {:#x} ({} {:#x})",
+                field.name, value, label, old
+            )?,
+            _ => writeln!(f, "    {} = {:#x}", field.name, value)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        let register = RegisterInfo::by_name("SYS_CFG").unwrap();
+
+        let mut baseline = RegisterDump::new();
+        baseline.push(*register, &[0x88, 0x06, 0x00, 0x00]);
+        let mut dump = RegisterDump::new();
+        dump.push(*register, &[0x89, 0x06, 0x00, 0x00]);
+
+        assert_eq!(dump.diff(&dump).to_string(), "");
+
+        let diff = dump.diff(&baseline).to_string();
+        assert!(diff.starts_with("SYS_CFG (0x00:0x10) = 0x00000689\n"));
+        assert!(diff.contains("  * ffen = 0x1 (was 0x0)\n"));
+        assert!(diff.contains("    dis_fcs_tx = 0x0\n"));
+    }
+
+    #[test]
+    fn reset_values() {
+        let mut dump = RegisterDump::new();
+        dump.push(
+            *RegisterInfo::by_name("LED_CTRL").unwrap(),
+            &[0x20, 0, 0, 0],
+        );
+        dump.push(
+            *RegisterInfo::by_name("PANADR").unwrap(),
+            &[0x34, 0x12, 0xFF, 0xFF],
+        );
+        dump.push(
+            *RegisterInfo::by_name("SYS_CFG").unwrap(),
+            &[0x88, 0x06, 0, 0],
+        );
+
+        let text = dump.to_string();
+        assert!(text.starts_with("LED_CTRL (0x11:0x16) = 0x00000020\n    blink_tim = 0x20\n"));
+        assert!(text.contains("\n* PANADR (0x00:0x0C) = 0xffff1234\n"));
+        assert!(text.contains("\n  * short_addr = 0x1234 (reset 0xffff)\n    pan_id = 0xffff\n"));
+        // Registers without a declared reset value aren't marked
+        assert!(text.contains("\nSYS_CFG (0x00:0x10) = 0x00000688\n"));
+    }
+}
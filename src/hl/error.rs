@@ -59,9 +59,12 @@ where
 
     /// A delayed frame could not be sent in time
     ///
-    /// Please note that the frame was still sent. Replies could still arrive,
-    /// and if it was a ranging frame, the resulting range measurement will be
-    /// wrong.
+    /// The DW3000 raised the half period delay warning, the send time was
+    /// already in the past when the transmission was started. The frame is
+    /// only sent once the system time wraps around, call
+    /// [`DW3000::finish_sending`] to abort it.
+    ///
+    /// [`DW3000::finish_sending`]: crate::DW3000::finish_sending
     DelayedSendTooLate,
 
     /// Transmitter could not power up in time for delayed send
@@ -92,6 +95,24 @@ where
 
     /// Failed to calibrate the PGF values
     PGFCalibrationFailed,
+
+    /// The DW3000 did not finish an operation in time
+    Timeout,
+
+    /// The programmed OTP word did not read back the expected value
+    OtpVerificationFailed,
+
+    /// Waiting for the IRQ line failed
+    Irq,
+
+    /// Another host accessed the DW3000 at the same time
+    SpiCollision {
+        /// The content of `SPI_COLLISION`
+        status: u8,
+    },
+
+    /// The SPI semaphore is held by the other host
+    SpiSemaphoreRefused,
 }
 
 impl<SPI> From<ll::Error<SPI>> for Error<SPI>
@@ -148,6 +169,13 @@ where
             }
             Error::InitializationFailed => write!(f, "InitializationFailed"),
             Error::PGFCalibrationFailed => write!(f, "PGFCalibrationFailed"),
+            Error::Timeout => write!(f, "Timeout"),
+            Error::OtpVerificationFailed => write!(f, "OtpVerificationFailed"),
+            Error::Irq => write!(f, "Irq"),
+            Error::SpiCollision { status } => {
+                write!(f, "SpiCollision {{ status: {:?} }}", status)
+            }
+            Error::SpiSemaphoreRefused => write!(f, "SpiSemaphoreRefused"),
         }
     }
 }
@@ -187,6 +215,13 @@ where
             }
             Error::InitializationFailed => defmt::write!(f, "InitializationFailed"),
             Error::PGFCalibrationFailed => defmt::write!(f, "PGFCalibrationFailed"),
+            Error::Timeout => defmt::write!(f, "Timeout"),
+            Error::OtpVerificationFailed => defmt::write!(f, "OtpVerificationFailed"),
+            Error::Irq => defmt::write!(f, "Irq"),
+            Error::SpiCollision { status } => {
+                defmt::write!(f, "SpiCollision {{ status: {:?} }}", status)
+            }
+            Error::SpiSemaphoreRefused => defmt::write!(f, "SpiSemaphoreRefused"),
         }
     }
 }
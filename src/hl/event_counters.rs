@@ -0,0 +1,142 @@
+//! Statistics collected by the DW3000's event counters
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::Awake;
+use crate::{maybe_async_attr, spi_type, Error, DW3000};
+
+/// Number of `EVC_CTRL` reads after which clearing the counters is abandoned
+///
+/// `EVC_CLR` clears itself within a few clock cycles, so this only triggers
+/// if the DW3000 is unresponsive.
+const EVC_CLR_POLLS: u8 = 16;
+
+/// A snapshot of the event counters
+///
+/// The 12-bit counters saturate at 4095, the 8-bit ones at 255. By default,
+/// the counters are cleared before every transmission, see
+/// [`Config::clear_event_counters`].
+///
+/// [`Config::clear_event_counters`]: crate::Config::clear_event_counters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct EventCounters {
+    /// PHY header errors
+    pub phr_errors: u16,
+    /// Reed Solomon decoder (sync loss) errors
+    pub reed_solomon_errors: u16,
+    /// Frames received with a good FCS
+    pub fcs_good: u16,
+    /// Frames received with an FCS error
+    pub fcs_errors: u16,
+    /// Frames rejected by frame filtering
+    pub frame_filter_rejections: u8,
+    /// Receiver overruns
+    pub rx_overruns: u8,
+    /// SFD timeouts
+    pub sfd_timeouts: u16,
+    /// Preamble detection timeouts
+    pub preamble_timeouts: u16,
+    /// Frame wait timeouts
+    pub frame_wait_timeouts: u8,
+    /// Frames sent
+    pub tx_frames_sent: u16,
+    /// Half period warnings, i.e. delayed transmissions that started late
+    pub half_period_warnings: u8,
+    /// SPI write CRC errors
+    pub spi_write_crc_errors: u8,
+    /// STS quality errors
+    pub sts_quality_errors: u8,
+    /// Low voltage warnings
+    pub low_voltage_warnings: u8,
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Reads all event counters
+    ///
+    /// The counters only count while they are enabled, which happens on the
+    /// first transmission or with [`DW3000::reset_event_counters`].
+    #[maybe_async_attr]
+    pub async fn read_event_counters(&mut self) -> Result<EventCounters, Error<SPI>> {
+        Ok(EventCounters {
+            phr_errors: self.ll.evc_phe().read().await?.value(),
+            reed_solomon_errors: self.ll.evc_rse().read().await?.value(),
+            fcs_good: self.ll.evc_fcg().read().await?.value(),
+            fcs_errors: self.ll.evc_fce().read().await?.value(),
+            frame_filter_rejections: self.ll.evc_ffr().read().await?.value(),
+            rx_overruns: self.ll.evc_ovr().read().await?.value(),
+            sfd_timeouts: self.ll.evc_sto().read().await?.value(),
+            preamble_timeouts: self.ll.evc_pto().read().await?.value(),
+            frame_wait_timeouts: self.ll.evc_fwto().read().await?.value(),
+            tx_frames_sent: self.ll.evc_txfs().read().await?.value(),
+            half_period_warnings: self.ll.evc_hpw().read().await?.value(),
+            spi_write_crc_errors: self.ll.evc_swce().read().await?.value(),
+            sts_quality_errors: self.ll.evc_cpqe().read().await?.value(),
+            low_voltage_warnings: self.ll.evc_vwarn().read().await?.value(),
+        })
+    }
+
+    /// Clears all event counters and enables them
+    ///
+    /// Returns [`Error::Timeout`] if the counters aren't cleared.
+    #[maybe_async_attr]
+    pub async fn reset_event_counters(&mut self) -> Result<(), Error<SPI>> {
+        self.ll.evc_ctrl().write(|w| w.evc_clr(0b1)).await?;
+
+        let mut polls = 0;
+        while self.ll.evc_ctrl().read().await?.evc_clr() == 0b1 {
+            polls += 1;
+            if polls == EVC_CLR_POLLS {
+                return Err(Error::Timeout);
+            }
+        }
+
+        self.ll.evc_ctrl().write(|w| w.evc_en(0b1)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ll, Ready, DW3000};
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_reset_event_counters_timeout() {
+        let mut expectations = vec![
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+        ];
+        // EVC_CLR never clears itself
+        for _ in 0..super::EVC_CLR_POLLS {
+            expectations.extend([
+                SpiTransaction::transaction_start(),
+                SpiTransaction::transfer_in_place(vec![0x5E, 0x00, 0], vec![0, 0, 0x02]),
+                SpiTransaction::transaction_end(),
+            ]);
+        }
+        let spi = SpiMock::new(&expectations);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let result = dw3000.reset_event_counters().await;
+        assert!(matches!(result, Err(crate::Error::Timeout)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -0,0 +1,417 @@
+//! System events and interrupt handling
+
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::Awake;
+use crate::{maybe_async_attr, spi_type, Error, DW3000};
+
+/// The events that `r_wait` checks
+pub(crate) const RX_WAIT_EVENTS: Events = Events::from_bits_truncate(
+    Events::RXFCG.bits()
+        | Events::RXFCE.bits()
+        | Events::RXPHE.bits()
+        | Events::RXFSL.bits()
+        | Events::RXSTO.bits()
+        | Events::ARFE.bits()
+        | Events::RXFTO.bits()
+        | Events::RXOVRR.bits()
+        | Events::RXPTO.bits(),
+);
+
+/// A set of system events, as reported in `SYS_STATUS`
+///
+/// The bit positions match `SYS_STATUS` and `SYS_ENABLE`. Combine events
+/// with `|`, for example `Events::TXFRS | Events::RXFCG`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct Events(u64);
+
+impl Events {
+    /// Interrupt request status, set while any enabled event is pending
+    pub const IRQS: Events = Events(1 << 0);
+    /// Clock PLL lock
+    pub const CPLOCK: Events = Events(1 << 1);
+    /// SPI CRC error
+    pub const SPICRCE: Events = Events(1 << 2);
+    /// Automatic acknowledge trigger
+    pub const AAT: Events = Events(1 << 3);
+    /// TX frame begins
+    pub const TXFRB: Events = Events(1 << 4);
+    /// TX preamble sent
+    pub const TXPRS: Events = Events(1 << 5);
+    /// TX PHY header sent
+    pub const TXPHS: Events = Events(1 << 6);
+    /// TX frame sent
+    pub const TXFRS: Events = Events(1 << 7);
+    /// RX preamble detected
+    pub const RXPRD: Events = Events(1 << 8);
+    /// RX SFD detected
+    pub const RXSFDD: Events = Events(1 << 9);
+    /// CIA processing done
+    pub const CIADONE: Events = Events(1 << 10);
+    /// RX PHY header detected
+    pub const RXPHD: Events = Events(1 << 11);
+    /// RX PHY header error
+    pub const RXPHE: Events = Events(1 << 12);
+    /// RX data frame ready
+    pub const RXFR: Events = Events(1 << 13);
+    /// RX FCS good
+    pub const RXFCG: Events = Events(1 << 14);
+    /// RX FCS error
+    pub const RXFCE: Events = Events(1 << 15);
+    /// RX Reed Solomon frame sync loss
+    pub const RXFSL: Events = Events(1 << 16);
+    /// RX frame wait timeout
+    pub const RXFTO: Events = Events(1 << 17);
+    /// Leading edge detection processing error
+    pub const CIAERR: Events = Events(1 << 18);
+    /// Low voltage warning
+    pub const VWARN: Events = Events(1 << 19);
+    /// RX overrun
+    pub const RXOVRR: Events = Events(1 << 20);
+    /// Preamble detection timeout
+    pub const RXPTO: Events = Events(1 << 21);
+    /// SPI ready for host access
+    pub const SPIRDY: Events = Events(1 << 23);
+    /// Entered the IDLE_RC state
+    pub const RCINIT: Events = Events(1 << 24);
+    /// Clock PLL losing lock
+    pub const PLL_HILO: Events = Events(1 << 25);
+    /// RX SFD timeout
+    pub const RXSTO: Events = Events(1 << 26);
+    /// Half period delay warning
+    pub const HPDWARN: Events = Events(1 << 27);
+    /// STS quality error
+    pub const CPERR: Events = Events(1 << 28);
+    /// Automatic frame filtering rejection
+    pub const ARFE: Events = Events(1 << 29);
+    /// RX preamble rejection
+    pub const RXPREJ: Events = Events(1 << 33);
+    /// Voltage or temperature variation detected
+    pub const VT_DET: Events = Events(1 << 36);
+    /// GPIO interrupt
+    pub const GPIOIRQ: Events = Events(1 << 37);
+    /// AES-DMA operation complete
+    pub const AES_DONE: Events = Events(1 << 38);
+    /// AES-DMA error
+    pub const AES_ERR: Events = Events(1 << 39);
+    /// Command error
+    pub const CMD_ERR: Events = Events(1 << 40);
+    /// SPI overflow error
+    pub const SPI_OVF: Events = Events(1 << 41);
+    /// SPI underflow error
+    pub const SPI_UNF: Events = Events(1 << 42);
+    /// SPI collision error
+    pub const SPIERR: Events = Events(1 << 43);
+    /// CCA failed, the frame was not sent
+    pub const CCA_FAIL: Events = Events(1 << 44);
+
+    /// All events that end a transmission
+    pub const TX_DONE: Events = Events::TXFRS;
+    /// All events that indicate a good frame
+    pub const RX_OK: Events = Events(Events::RXFCG.0 | Events::RXFR.0);
+    /// All events that indicate a reception error
+    pub const RX_ERROR: Events = Events(
+        Events::RXPHE.0
+            | Events::RXFCE.0
+            | Events::RXFSL.0
+            | Events::CIAERR.0
+            | Events::RXOVRR.0
+            | Events::RXSTO.0
+            | Events::CPERR.0
+            | Events::ARFE.0,
+    );
+    /// All events that indicate a reception timeout
+    pub const RX_TIMEOUT: Events = Events(Events::RXFTO.0 | Events::RXPTO.0);
+    /// All events that are summarised in the `FINT_STAT` register
+    pub const FAST_STATUS: Events = Events(
+        Events::AAT.0
+            | Events::TXFRB.0
+            | Events::TXPRS.0
+            | Events::TXPHS.0
+            | Events::TXFRS.0
+            | Events::CIADONE.0
+            | Events::RXPHE.0
+            | Events::RXFR.0
+            | Events::RXFCG.0
+            | Events::RXFCE.0
+            | Events::RXFSL.0
+            | Events::RXFTO.0
+            | Events::CIAERR.0
+            | Events::VWARN.0
+            | Events::RXOVRR.0
+            | Events::RXPTO.0
+            | Events::SPIRDY.0
+            | Events::RCINIT.0
+            | Events::PLL_HILO.0
+            | Events::RXSTO.0
+            | Events::ARFE.0
+            | Events::VT_DET.0
+            | Events::GPIOIRQ.0
+            | Events::AES_ERR.0
+            | Events::CMD_ERR.0
+            | Events::SPI_OVF.0
+            | Events::SPI_UNF.0
+            | Events::SPIERR.0
+            | Events::CCA_FAIL.0,
+    );
+    /// All events
+    pub const ALL: Events = Events(0x1FF2_3FBF_FFFE);
+
+    /// Returns the empty set
+    pub const fn empty() -> Self {
+        Events(0)
+    }
+
+    /// Creates a set from the raw `SYS_STATUS` value
+    ///
+    /// Bits that don't correspond to an event are dropped.
+    pub const fn from_bits_truncate(bits: u64) -> Self {
+        Events(bits & (Events::ALL.0 | Events::IRQS.0))
+    }
+
+    /// Returns the raw `SYS_STATUS` value
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if no event is set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all events in `other` are set
+    pub const fn contains(&self, other: Events) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if any event in `other` is set
+    pub const fn intersects(&self, other: Events) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Events {
+    type Output = Events;
+
+    fn bitor(self, rhs: Events) -> Events {
+        Events(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Events {
+    fn bitor_assign(&mut self, rhs: Events) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Events {
+    type Output = Events;
+
+    fn bitand(self, rhs: Events) -> Events {
+        Events(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Events {
+    fn bitand_assign(&mut self, rhs: Events) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl Not for Events {
+    type Output = Events;
+
+    fn not(self) -> Events {
+        Events(!self.0 & (Events::ALL.0 | Events::IRQS.0))
+    }
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Selects the events that assert the IRQ line
+    ///
+    /// Overwrites any interrupt mask that was previously set, including the
+    /// ones set by [`DW3000::enable_tx_interrupts`] and
+    /// [`DW3000::enable_rx_interrupts`].
+    #[maybe_async_attr]
+    pub async fn set_interrupt_mask(&mut self, events: Events) -> Result<(), Error<SPI>> {
+        let mask = (events & Events::ALL).bits();
+        self.ll.sys_enable().write(|w| w.value(mask)).await?;
+
+        Ok(())
+    }
+
+    /// Returns the events that assert the IRQ line
+    #[maybe_async_attr]
+    pub async fn get_interrupt_mask(&mut self) -> Result<Events, Error<SPI>> {
+        let mask = self.ll.sys_enable().read().await?.value();
+
+        Ok(Events::from_bits_truncate(mask) & Events::ALL)
+    }
+
+    /// Reads all pending events without clearing them
+    #[maybe_async_attr]
+    pub async fn read_events(&mut self) -> Result<Events, Error<SPI>> {
+        let status = self.ll.sys_status().read().await?.value();
+
+        Ok(Events::from_bits_truncate(status))
+    }
+
+    /// Clears the given events
+    #[maybe_async_attr]
+    pub async fn clear_events(&mut self, events: Events) -> Result<(), Error<SPI>> {
+        let events = (events & Events::ALL).bits();
+        self.ll.sys_status().write(|w| w.value(events)).await?;
+
+        Ok(())
+    }
+
+    /// Handles an interrupt
+    ///
+    /// Call this from the handler of the IRQ line, with the `mask` that was
+    /// passed to [`DW3000::set_interrupt_mask`], which saves reading
+    /// `SYS_ENABLE` on every interrupt. Returns the pending events that are
+    /// in `mask`, and clears them, which deasserts the IRQ line. Events
+    /// outside of the mask are left pending, so that the `wait` methods keep
+    /// working.
+    ///
+    /// If all events in `mask` are in [`Events::FAST_STATUS`], the 1-byte
+    /// `FINT_STAT` register is checked first, which saves reading
+    /// `SYS_STATUS` when nothing is pending.
+    #[maybe_async_attr]
+    pub async fn service_irq(&mut self, mask: Events) -> Result<Events, Error<SPI>> {
+        let mask = mask & Events::ALL;
+
+        if Events::FAST_STATUS.contains(mask) && self.ll.fint_stat().read().await?.value() == 0 {
+            return Ok(Events::empty());
+        }
+
+        let events = self.read_events().await? & mask;
+        if !events.is_empty() {
+            self.clear_events(events).await?;
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ll, Ready};
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[test]
+    fn events() {
+        let events = Events::TXFRS | Events::RXFCG;
+        assert_eq!(events.bits(), 0x4080);
+        assert!(events.contains(Events::TXFRS));
+        assert!(!events.contains(Events::RX_OK));
+        assert!(events.intersects(Events::RX_OK));
+        assert_eq!(events & Events::TX_DONE, Events::TXFRS);
+
+        assert!(Events::ALL.contains(Events::FAST_STATUS));
+        assert!(!Events::ALL.contains(Events::IRQS));
+        assert_eq!(
+            Events::from_bits_truncate(1 << 22 | 1 << 37),
+            Events::GPIOIRQ
+        );
+        assert_eq!(!Events::ALL, Events::IRQS);
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_set_interrupt_mask() {
+        let spi = SpiMock::new(&[
+            // IRQS is not an event and is dropped from the mask
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xF0, 0x80, 0x40, 0, 0, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        dw3000
+            .set_interrupt_mask(Events::TXFRS | Events::RXFCG | Events::IRQS)
+            .await
+            .unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_service_irq_nothing_pending() {
+        let spi = SpiMock::new(&[
+            // Only FINT_STAT is read when it reports no fast events
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x7E, 0x00, 0], vec![0, 0, 0x00]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let events = dw3000
+            .service_irq(Events::TXFRS | Events::RXFCG)
+            .await
+            .unwrap();
+        assert_eq!(events, Events::empty());
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_service_irq() {
+        let spi = SpiMock::new(&[
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x7E, 0x00, 0], vec![0, 0, 0x01]),
+            SpiTransaction::transaction_end(),
+            // TXFRS, RXPRD and RXFCG are pending
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x80, 0x41, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            // Only the masked events are cleared, RXPRD is left pending
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0x80, 0x40, 0, 0, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let events = dw3000
+            .service_irq(Events::TXFRS | Events::RXFCG)
+            .await
+            .unwrap();
+        assert_eq!(events, Events::TXFRS | Events::RXFCG);
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -0,0 +1,203 @@
+//! Control of an external power amplifier and low noise amplifier
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::{Gpio4Function, Gpio5Function, Gpio6Function, GpioFunction};
+use crate::{maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// `TXFSEQ` value with fine grain TX sequencing enabled (reset default)
+pub(super) const TXFSEQ_ENABLED: u32 = 0x04D2_8874;
+/// `TXFSEQ` value with fine grain TX sequencing disabled
+pub(super) const TXFSEQ_DISABLED: u32 = 0x00D2_0874;
+
+/// Configuration of an external RF front end
+///
+/// The DW3000 drives the front end through fixed pins: GPIO4 (EXTPA) enables
+/// the power amplifier, GPIO5 (EXTTXE) selects the TX path of the RF switch and
+/// GPIO6 (EXTRXE) enables the low noise amplifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct ExternalFrontEnd {
+    /// Drive the PA enable on GPIO4 and the TX switch on GPIO5
+    ///
+    /// With fine grain TX sequencing, the PA would only be enabled shortly
+    /// before the preamble is sent, which is too late for external PAs. As
+    /// the user manual requires, fine grain TX sequencing is disabled while
+    /// the PA is driven, unless `tx_lead_time` is set.
+    pub pa_enable_pin: bool,
+    /// Drive the LNA enable on GPIO6
+    pub lna_enable_pin: bool,
+    /// The `TXFSEQ` sequence that enables the PA ahead of the transmission
+    ///
+    /// The user manual doesn't document the fields of `TXFSEQ`, so the lead
+    /// time is given as the whole register value, for example from the
+    /// datasheet of the front end module. If `None`, fine grain TX sequencing
+    /// is disabled while the PA is driven (`0x00D2_0874`, as Qorvo's
+    /// `dwt_setfinegraintxseq`).
+    pub tx_lead_time: Option<u32>,
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Configures the GPIOs and TX sequencing for an external PA and LNA
+    ///
+    /// Overwrites the functions of GPIO4, GPIO5 and GPIO6. `TXFSEQ` is set to
+    /// the `tx_lead_time` sequence if given. Otherwise, fine grain TX
+    /// sequencing is disabled if the PA is driven, and enabled if not.
+    #[maybe_async_attr]
+    pub async fn enable_external_pa_lna(
+        &mut self,
+        front_end: ExternalFrontEnd,
+    ) -> Result<(), Error<SPI>> {
+        let (pa, txe) = if front_end.pa_enable_pin {
+            (Gpio4Function::ExtPa, Gpio5Function::ExtTxe)
+        } else {
+            (Gpio4Function::Gpio, Gpio5Function::Gpio)
+        };
+        let rxe = if front_end.lna_enable_pin {
+            Gpio6Function::ExtRxe
+        } else {
+            Gpio6Function::Gpio
+        };
+
+        self.ll
+            .gpio_mode()
+            .modify(|_, w| w.msgp4(pa.mode()).msgp5(txe.mode()).msgp6(rxe.mode()))
+            .await?;
+
+        let txfseq = match front_end.tx_lead_time {
+            Some(txfseq) => txfseq,
+            None if front_end.pa_enable_pin => TXFSEQ_DISABLED,
+            None => TXFSEQ_ENABLED,
+        };
+        self.ll.txfseq().write(|w| w.value(txfseq)).await?;
+
+        Ok(())
+    }
+
+    /// Returns GPIO4, GPIO5 and GPIO6 to GPIO mode and re-enables fine grain
+    /// TX sequencing
+    #[maybe_async_attr]
+    pub async fn disable_external_pa_lna(&mut self) -> Result<(), Error<SPI>> {
+        self.ll
+            .gpio_mode()
+            .modify(|_, w| {
+                w.msgp4(Gpio4Function::Gpio.mode())
+                    .msgp5(Gpio5Function::Gpio.mode())
+                    .msgp6(Gpio6Function::Gpio.mode())
+            })
+            .await?;
+        self.ll.txfseq().write(|w| w.value(TXFSEQ_ENABLED)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_enable_external_pa_lna() {
+        let spi = SpiMock::new(&[
+            // Read GPIO_MODE, GPIO7 and GPIO8 keep their function
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4A, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x20, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+            // EXTPA, EXTTXE and EXTRXE
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x00, 0x00, 0x90, 0x24, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Fine grain TX sequencing disabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x74, 0x08, 0xD2, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Back to GPIO mode
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4A, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x90, 0x24, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x00, 0x00, 0x00, 0x20, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Fine grain TX sequencing enabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x74, 0x88, 0xD2, 0x04]),
+            SpiTransaction::transaction_end(),
+            // Only EXTRXE, fine grain TX sequencing stays enabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4A, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x20, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x00, 0x00, 0x00, 0x24, 0x01]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x74, 0x88, 0xD2, 0x04]),
+            SpiTransaction::transaction_end(),
+            // PA with a lead time, TXFSEQ is programmed as given
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4A, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x24, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x00, 0x00, 0x90, 0x20, 0x01]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x78, 0x08, 0xD2, 0x00]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        dw3000
+            .enable_external_pa_lna(ExternalFrontEnd {
+                pa_enable_pin: true,
+                lna_enable_pin: true,
+                tx_lead_time: None,
+            })
+            .await
+            .unwrap();
+        dw3000.disable_external_pa_lna().await.unwrap();
+        dw3000
+            .enable_external_pa_lna(ExternalFrontEnd {
+                pa_enable_pin: false,
+                lna_enable_pin: true,
+                tx_lead_time: None,
+            })
+            .await
+            .unwrap();
+        dw3000
+            .enable_external_pa_lna(ExternalFrontEnd {
+                pa_enable_pin: true,
+                lna_enable_pin: false,
+                tx_lead_time: Some(0x00D2_0878),
+            })
+            .await
+            .unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -0,0 +1,916 @@
+//! GPIO pins and their alternate functions
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::Awake;
+use crate::{maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// The GPIO pins of the DW3000
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+#[repr(u8)]
+pub enum GpioPin {
+    /// GPIO0 / RXOKLED / PDOA_SW0
+    Gpio0 = 0,
+    /// GPIO1 / SFDLED / PDOA_SW1
+    Gpio1 = 1,
+    /// GPIO2 / RXLED / PDOA_SW2
+    Gpio2 = 2,
+    /// GPIO3 / TXLED / PDOA_SW3
+    Gpio3 = 3,
+    /// GPIO4 / EXTPA / IRQ
+    Gpio4 = 4,
+    /// GPIO5 / EXTTXE
+    Gpio5 = 5,
+    /// GPIO6 / EXTRXE
+    Gpio6 = 6,
+    /// SYNC / GPIO7
+    Gpio7 = 7,
+    /// IRQ / GPIO8
+    Gpio8 = 8,
+}
+
+impl GpioPin {
+    /// All pins, in order
+    pub const ALL: [GpioPin; 9] = [
+        GpioPin::Gpio0,
+        GpioPin::Gpio1,
+        GpioPin::Gpio2,
+        GpioPin::Gpio3,
+        GpioPin::Gpio4,
+        GpioPin::Gpio5,
+        GpioPin::Gpio6,
+        GpioPin::Gpio7,
+        GpioPin::Gpio8,
+    ];
+
+    /// Returns the mask of the pin in the one bit per pin registers
+    fn mask(self) -> u16 {
+        1 << self as u8
+    }
+
+    /// Returns the raw `GPIO_MODE` value that selects the plain GPIO function
+    fn gpio_mode(self) -> u8 {
+        match self {
+            GpioPin::Gpio7 | GpioPin::Gpio8 => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// An alternate function of a GPIO pin
+///
+/// Every pin has its own enum, so that functions a pin does not support
+/// are rejected at compile time.
+pub trait GpioFunction: Copy {
+    /// The pin this function belongs to
+    const PIN: GpioPin;
+
+    /// Returns the raw `GPIO_MODE` value of the function
+    fn mode(self) -> u8;
+
+    /// Decodes the raw `GPIO_MODE` value of the pin
+    fn from_mode(mode: u8) -> Option<Self>;
+}
+
+macro_rules! gpio_function {
+    (
+        $(#[$attr:meta])*
+        $name:ident($pin:ident), default $default:ident {
+            $($(#[$variant_attr:meta])* $variant:ident = $mode:expr,)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[cfg_attr(feature = "defmt", derive(Format))]
+        #[repr(u8)]
+        pub enum $name {
+            $($(#[$variant_attr])* $variant = $mode,)*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::$default
+            }
+        }
+
+        impl GpioFunction for $name {
+            const PIN: GpioPin = GpioPin::$pin;
+
+            fn mode(self) -> u8 {
+                self as u8
+            }
+
+            fn from_mode(mode: u8) -> Option<Self> {
+                match mode {
+                    $($mode => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+gpio_function! {
+    /// Functions of GPIO0
+    Gpio0Function(Gpio0), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// Lights up on good frame reception
+        RxOkLed = 1,
+        /// PDoA antenna switch control 0
+        PdoaSw = 2,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO1
+    Gpio1Function(Gpio1), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// Lights up on SFD detection
+        SfdLed = 1,
+        /// PDoA antenna switch control 1
+        PdoaSw = 2,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO2
+    Gpio2Function(Gpio2), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// Lights up while the receiver is on
+        RxLed = 1,
+        /// PDoA antenna switch control 2
+        PdoaSw = 2,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO3
+    Gpio3Function(Gpio3), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// Lights up while transmitting
+        TxLed = 1,
+        /// PDoA antenna switch control 3
+        PdoaSw = 2,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO4
+    Gpio4Function(Gpio4), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// External power amplifier enable
+        ExtPa = 1,
+        /// Interrupt output
+        Irq = 2,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO5
+    Gpio5Function(Gpio5), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// External transmit enable
+        ExtTxe = 1,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO6
+    Gpio6Function(Gpio6), default Gpio {
+        /// General purpose I/O
+        Gpio = 0,
+        /// External receive enable
+        ExtRxe = 1,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO7
+    Gpio7Function(Gpio7), default Sync {
+        /// External synchronisation input
+        Sync = 0,
+        /// General purpose I/O
+        Gpio = 1,
+    }
+}
+
+gpio_function! {
+    /// Functions of GPIO8
+    Gpio8Function(Gpio8), default Irq {
+        /// Interrupt output
+        Irq = 0,
+        /// General purpose I/O
+        Gpio = 1,
+    }
+}
+
+/// The direction of a pin in GPIO mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub enum GpioDirection {
+    /// The pin is an output
+    Output,
+    /// The pin is an input
+    #[default]
+    Input,
+}
+
+/// The functions of all GPIO pins
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct GpioFunctions {
+    /// Function of GPIO0
+    pub gpio0: Gpio0Function,
+    /// Function of GPIO1
+    pub gpio1: Gpio1Function,
+    /// Function of GPIO2
+    pub gpio2: Gpio2Function,
+    /// Function of GPIO3
+    pub gpio3: Gpio3Function,
+    /// Function of GPIO4
+    pub gpio4: Gpio4Function,
+    /// Function of GPIO5
+    pub gpio5: Gpio5Function,
+    /// Function of GPIO6
+    pub gpio6: Gpio6Function,
+    /// Function of GPIO7
+    pub gpio7: Gpio7Function,
+    /// Function of GPIO8
+    pub gpio8: Gpio8Function,
+}
+
+impl GpioFunctions {
+    /// Returns the raw `GPIO_MODE` value
+    fn to_register(self) -> u32 {
+        [
+            self.gpio0.mode(),
+            self.gpio1.mode(),
+            self.gpio2.mode(),
+            self.gpio3.mode(),
+            self.gpio4.mode(),
+            self.gpio5.mode(),
+            self.gpio6.mode(),
+            self.gpio7.mode(),
+            self.gpio8.mode(),
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |value, (pin, mode)| value | (*mode as u32) << (3 * pin))
+    }
+
+    /// Decodes the raw `GPIO_MODE` value
+    ///
+    /// Returns `None` if a pin uses a reserved mode.
+    fn from_register(value: u32) -> Option<Self> {
+        fn mode<F: GpioFunction>(value: u32) -> Option<F> {
+            F::from_mode((value >> (3 * F::PIN as u32) & 0x7) as u8)
+        }
+
+        Some(GpioFunctions {
+            gpio0: mode(value)?,
+            gpio1: mode(value)?,
+            gpio2: mode(value)?,
+            gpio3: mode(value)?,
+            gpio4: mode(value)?,
+            gpio5: mode(value)?,
+            gpio6: mode(value)?,
+            gpio7: mode(value)?,
+            gpio8: mode(value)?,
+        })
+    }
+}
+
+/// General configuration for GPIO
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct ConfigGPIOs {
+    /// Full (`true`) or reduced (`false`) drive strength of every pin
+    pub enabled: [bool; 9],
+    /// The function of every pin
+    pub functions: GpioFunctions,
+    /// The direction of every pin in GPIO mode
+    pub gpio_dir: [GpioDirection; 9],
+    /// The output level of every pin in GPIO mode
+    pub output: [bool; 9],
+}
+
+impl Default for ConfigGPIOs {
+    fn default() -> Self {
+        ConfigGPIOs {
+            enabled: [true; 9],
+            functions: GpioFunctions::default(),
+            gpio_dir: [GpioDirection::Input; 9],
+            output: [false; 9],
+        }
+    }
+}
+
+impl ConfigGPIOs {
+    /// Disables all 4 leds
+    pub fn disable_led() -> Self {
+        ConfigGPIOs {
+            enabled: [true, true, false, false, true, true, true, true, true],
+            functions: GpioFunctions {
+                gpio2: Gpio2Function::RxLed,
+                gpio3: Gpio3Function::TxLed,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+    /// Enables only RX and TX led
+    pub fn enable_led() -> Self {
+        ConfigGPIOs {
+            functions: GpioFunctions {
+                gpio2: Gpio2Function::RxLed,
+                gpio3: Gpio3Function::TxLed,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+    /// Set everything to 0
+    ///
+    /// GPIO0 to GPIO6 are outputs driven low, GPIO7 and GPIO8 keep their
+    /// SYNC and IRQ functions, which are mode 0.
+    pub fn all_0() -> Self {
+        ConfigGPIOs {
+            enabled: [false; 9],
+            functions: GpioFunctions::default(),
+            gpio_dir: [GpioDirection::Output; 9],
+            output: [false; 9],
+        }
+    }
+    /// Set everything to 1
+    #[deprecated(
+        note = "selects a mix of LED, front end and GPIO functions, build the config \
+                         from `GpioFunctions` instead"
+    )]
+    pub fn all_1() -> Self {
+        ConfigGPIOs {
+            enabled: [true; 9],
+            functions: GpioFunctions {
+                gpio0: Gpio0Function::RxOkLed,
+                gpio1: Gpio1Function::SfdLed,
+                gpio2: Gpio2Function::RxLed,
+                gpio3: Gpio3Function::TxLed,
+                gpio4: Gpio4Function::ExtPa,
+                gpio5: Gpio5Function::ExtTxe,
+                gpio6: Gpio6Function::ExtRxe,
+                gpio7: Gpio7Function::Gpio,
+                gpio8: Gpio8Function::Gpio,
+            },
+            gpio_dir: [GpioDirection::Input; 9],
+            output: [true; 9],
+        }
+    }
+    /// Custom config for debug
+    pub fn custom() -> Self {
+        let mut gpio_dir = [GpioDirection::Output; 9];
+        gpio_dir[0] = GpioDirection::Input;
+        gpio_dir[1] = GpioDirection::Input;
+
+        ConfigGPIOs {
+            enabled: [true, true, true, true, false, false, false, false, false],
+            functions: GpioFunctions::default(),
+            gpio_dir,
+            output: [false; 9],
+        }
+    }
+}
+
+/// Converts a one bit per pin register value to an array
+fn to_array(value: u16) -> [bool; 9] {
+    let mut pins = [false; 9];
+    for (pin, state) in pins.iter_mut().enumerate() {
+        *state = value >> pin & 0x1 == 1;
+    }
+    pins
+}
+
+/// Converts an array to a one bit per pin register value
+fn from_array(pins: [bool; 9]) -> u16 {
+    pins.iter()
+        .enumerate()
+        .fold(0, |value, (pin, state)| value | (*state as u16) << pin)
+}
+
+/// Sets or clears the bit of `pin` in `value`
+fn with_pin(value: u16, pin: GpioPin, state: bool) -> u16 {
+    (value & !pin.mask()) | ((state as u16) << pin as u8)
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Init GPIO WRT Config
+    #[maybe_async_attr]
+    pub async fn gpio_config(&mut self, config: ConfigGPIOs) -> Result<(), Error<SPI>> {
+        self.gpio_config_clocks().await?;
+
+        self.ll
+            .gpio_pull_en()
+            .modify(|_, w| w.value(from_array(config.enabled)))
+            .await?;
+        self.ll
+            .gpio_mode()
+            .modify(|_, w| w.value(config.functions.to_register()))
+            .await?;
+        self.ll
+            .gpio_dir()
+            .modify(|_, w| {
+                w.value(from_array(
+                    config.gpio_dir.map(|dir| dir == GpioDirection::Input),
+                ))
+            })
+            .await?;
+        self.ll
+            .gpio_out()
+            .modify(|_, w| w.value(from_array(config.output)))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Enable gpios clocks
+    #[maybe_async_attr]
+    pub async fn gpio_config_clocks(&mut self) -> Result<(), Error<SPI>> {
+        self.ll
+            .clk_ctrl()
+            .modify_masked(|w| {
+                w.gpio_clk_en(0b1)
+                    .gpio_dclk_en(0b1)
+                    .gpio_drst_n(0b1)
+                    .lp_clk_en(0b1)
+            })
+            .await?;
+
+        self.ll
+            .led_ctrl()
+            .modify(|_, w| w.blink_en(0b1).blink_tim(0x10).force_trig(0x0))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the drive strength of a single pin
+    #[maybe_async_attr]
+    pub async fn gpio_config_enable(
+        &mut self,
+        pin: GpioPin,
+        enable: bool,
+    ) -> Result<(), Error<SPI>> {
+        self.ll
+            .gpio_pull_en()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, enable)))
+            .await?;
+        Ok(())
+    }
+
+    /// Configures the function of a single pin
+    ///
+    /// ``` ignore
+    /// dw3000.gpio_config_mode(Gpio2Function::RxLed)?;
+    /// ```
+    #[maybe_async_attr]
+    pub async fn gpio_config_mode<F: GpioFunction>(
+        &mut self,
+        function: F,
+    ) -> Result<(), Error<SPI>> {
+        let shift = 3 * F::PIN as u32;
+
+        self.ll
+            .gpio_mode()
+            .modify(|r, w| {
+                w.value((r.value() & !(0x7 << shift)) | (function.mode() as u32) << shift)
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Configures direction for a single pin
+    #[maybe_async_attr]
+    pub async fn gpio_config_dir(
+        &mut self,
+        pin: GpioPin,
+        dir: GpioDirection,
+    ) -> Result<(), Error<SPI>> {
+        self.ll
+            .gpio_dir()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, dir == GpioDirection::Input)))
+            .await?;
+        Ok(())
+    }
+
+    /// Configures output for a single pin
+    #[maybe_async_attr]
+    pub async fn gpio_config_out(&mut self, pin: GpioPin, output: bool) -> Result<(), Error<SPI>> {
+        self.ll
+            .gpio_out()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, output)))
+            .await?;
+        Ok(())
+    }
+
+    /// Returns GPIO config
+    #[maybe_async_attr]
+    pub async fn get_gpio_config(&mut self) -> Result<ConfigGPIOs, Error<SPI>> {
+        let enabled = self.get_gpio_enabled().await?;
+        let functions = self.get_gpio_mode().await?;
+        let gpio_dir = self.get_gpio_dir().await?;
+        let output = self.get_gpio_out().await?;
+
+        Ok(ConfigGPIOs {
+            enabled,
+            functions,
+            gpio_dir,
+            output,
+        })
+    }
+
+    /// Returns current gpio drive strength
+    #[maybe_async_attr]
+    pub async fn get_gpio_enabled(&mut self) -> Result<[bool; 9], Error<SPI>> {
+        Ok(to_array(self.ll.gpio_pull_en().read().await?.value()))
+    }
+
+    /// Returns current gpio pin functions
+    ///
+    /// Returns [`Error::InvalidConfiguration`] if a pin uses a reserved mode.
+    #[maybe_async_attr]
+    pub async fn get_gpio_mode(&mut self) -> Result<GpioFunctions, Error<SPI>> {
+        let value = self.ll.gpio_mode().read().await?.value();
+
+        GpioFunctions::from_register(value).ok_or(Error::InvalidConfiguration)
+    }
+
+    /// Returns current gpio dir
+    #[maybe_async_attr]
+    pub async fn get_gpio_dir(&mut self) -> Result<[GpioDirection; 9], Error<SPI>> {
+        let inputs = to_array(self.ll.gpio_dir().read().await?.value());
+
+        Ok(inputs.map(|input| {
+            if input {
+                GpioDirection::Input
+            } else {
+                GpioDirection::Output
+            }
+        }))
+    }
+
+    /// Returns current output
+    #[maybe_async_attr]
+    pub async fn get_gpio_out(&mut self) -> Result<[bool; 9], Error<SPI>> {
+        Ok(to_array(self.ll.gpio_out().read().await?.value()))
+    }
+
+    /// Returns current raw state / input
+    #[maybe_async_attr]
+    pub async fn get_gpio_raw_state(&mut self) -> Result<[bool; 9], Error<SPI>> {
+        Ok(to_array(self.ll.gpio_raw().read().await?.value()))
+    }
+}
+
+/// The condition that triggers a GPIO interrupt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub enum GpioInterruptTrigger {
+    /// Trigger while the pin is high
+    HighLevel,
+    /// Trigger while the pin is low
+    LowLevel,
+    /// Trigger on a rising edge
+    RisingEdge,
+    /// Trigger on a falling edge
+    FallingEdge,
+    /// Trigger on both edges
+    BothEdges,
+}
+
+/// Interrupt configuration of a single GPIO pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct GpioInterruptConfig {
+    /// The condition that triggers the interrupt
+    pub trigger: GpioInterruptTrigger,
+    /// Debounce the input with the kilohertz clock
+    ///
+    /// Useful for mechanical buttons, but delays the interrupt by a few
+    /// milliseconds.
+    pub debounce: bool,
+}
+
+impl GpioInterruptConfig {
+    /// Returns the (mode, sense, both edges) register bits
+    fn bits(&self) -> (bool, bool, bool) {
+        match self.trigger {
+            GpioInterruptTrigger::HighLevel => (false, false, false),
+            GpioInterruptTrigger::LowLevel => (false, true, false),
+            GpioInterruptTrigger::RisingEdge => (true, false, false),
+            GpioInterruptTrigger::FallingEdge => (true, true, false),
+            GpioInterruptTrigger::BothEdges => (true, false, true),
+        }
+    }
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Returns `true` if a GPIO interrupt is pending
+    ///
+    /// This is the `GPIOIRQ` event in `SYS_STATUS`, which is raised on the
+    /// IRQ line if enabled with [`DW3000::enable_gpio_interrupts`].
+    #[maybe_async_attr]
+    pub async fn gpio_interrupt_pending(&mut self) -> Result<bool, Error<SPI>> {
+        Ok(self.ll.sys_status().read().await?.gpioirq() == 1)
+    }
+
+    /// Returns the pins that have a pending interrupt
+    #[maybe_async_attr]
+    pub async fn read_gpio_interrupts(&mut self) -> Result<[bool; 9], Error<SPI>> {
+        Ok(to_array(self.ll.gpio_ists().read().await?.value()))
+    }
+
+    /// Clears all pending GPIO interrupts and the `GPIOIRQ` event
+    #[maybe_async_attr]
+    pub async fn clear_gpio_interrupts(&mut self) -> Result<(), Error<SPI>> {
+        let ists = self.ll.gpio_ists().read().await?.value();
+
+        self.ll.gpio_iclr().write(|w| w.value(ists)).await?;
+        self.ll.sys_status().write(|w| w.gpioirq(0b1)).await?;
+
+        Ok(())
+    }
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Configures the interrupt of a single GPIO pin
+    ///
+    /// Passing `None` disables the interrupt of the pin. The pin has to be
+    /// configured as a GPIO input (see [`DW3000::gpio_config`]).
+    #[maybe_async_attr]
+    pub async fn configure_gpio_interrupt(
+        &mut self,
+        pin: GpioPin,
+        config: Option<GpioInterruptConfig>,
+    ) -> Result<(), Error<SPI>> {
+        let Some(config) = config else {
+            self.ll
+                .gpio_irqe()
+                .modify(|r, w| w.value(with_pin(r.value(), pin, false)))
+                .await?;
+            return Ok(());
+        };
+
+        let (mode, sense, both_edges) = config.bits();
+
+        if config.debounce {
+            self.ll
+                .clk_ctrl()
+                .modify_masked(|w| {
+                    w.gpio_clk_en(0b1)
+                        .gpio_dclk_en(0b1)
+                        .gpio_drst_n(0b1)
+                        .lp_clk_en(0b1)
+                })
+                .await?;
+        } else {
+            self.ll
+                .clk_ctrl()
+                .modify_masked(|w| w.gpio_clk_en(0b1))
+                .await?;
+        }
+
+        self.ll
+            .gpio_imode()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, mode)))
+            .await?;
+        self.ll
+            .gpio_isen()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, sense)))
+            .await?;
+        self.ll
+            .gpio_ibes()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, both_edges)))
+            .await?;
+        self.ll
+            .gpio_idbe()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, config.debounce)))
+            .await?;
+
+        // Clear a stale latch before enabling the interrupt
+        self.ll.gpio_iclr().write(|w| w.value(pin.mask())).await?;
+        self.ll
+            .gpio_irqe()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, true)))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Enables the `GPIOIRQ` event on the IRQ line
+    #[maybe_async_attr]
+    pub async fn enable_gpio_interrupts(&mut self) -> Result<(), Error<SPI>> {
+        self.ll
+            .sys_enable()
+            .modify_masked(|w| w.gpioirq_en(0b1))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Borrows a pin in GPIO mode as a digital pin
+    ///
+    /// Returns [`Error::InvalidConfiguration`] if the pin is configured for an
+    /// alternate function.
+    #[maybe_async_attr]
+    pub async fn gpio_pin(&mut self, pin: GpioPin) -> Result<Gpio<'_, SPI>, Error<SPI>> {
+        let mode = self.ll.gpio_mode().read().await?.value() >> (3 * pin as u32) & 0x7;
+        if mode as u8 != pin.gpio_mode() {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        Ok(Gpio {
+            ll: &mut self.ll,
+            pin,
+        })
+    }
+}
+
+/// A pin in GPIO mode, borrowed from the DW3000
+///
+/// The direction of the pin has to be set with [`DW3000::gpio_config_dir`].
+///
+/// Without the `async` feature, the pin implements the `embedded_hal` digital
+/// pin traits. `embedded-hal-async` has no traits for digital pins, so with
+/// the `async` feature, use the async methods of the same names instead.
+pub struct Gpio<'a, SPI> {
+    ll: &'a mut crate::ll::DW3000<SPI>,
+    pin: GpioPin,
+}
+
+impl<SPI> Gpio<'_, SPI>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Returns `true` if the pin is high
+    #[maybe_async_attr]
+    pub async fn is_high(&mut self) -> Result<bool, Error<SPI>> {
+        Ok(self.ll.gpio_raw().read().await?.value() & self.pin.mask() != 0)
+    }
+
+    /// Returns `true` if the pin is low
+    #[maybe_async_attr]
+    pub async fn is_low(&mut self) -> Result<bool, Error<SPI>> {
+        Ok(!self.is_high().await?)
+    }
+
+    /// Drives the pin low
+    #[maybe_async_attr]
+    pub async fn set_low(&mut self) -> Result<(), Error<SPI>> {
+        let pin = self.pin;
+        self.ll
+            .gpio_out()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, false)))
+            .await?;
+        Ok(())
+    }
+
+    /// Drives the pin high
+    #[maybe_async_attr]
+    pub async fn set_high(&mut self) -> Result<(), Error<SPI>> {
+        let pin = self.pin;
+        self.ll
+            .gpio_out()
+            .modify(|r, w| w.value(with_pin(r.value(), pin, true)))
+            .await?;
+        Ok(())
+    }
+}
+
+impl<SPI> embedded_hal::digital::Error for Error<SPI>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+impl<SPI> embedded_hal::digital::ErrorType for Gpio<'_, SPI>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    type Error = Error<SPI>;
+}
+
+#[cfg(not(feature = "async"))]
+impl<SPI> embedded_hal::digital::InputPin for Gpio<'_, SPI>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Gpio::is_high(self)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Gpio::is_low(self)
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl<SPI> embedded_hal::digital::OutputPin for Gpio<'_, SPI>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Gpio::set_low(self)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Gpio::set_high(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ll, Ready};
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+    #[test]
+    fn gpio_functions_roundtrip() {
+        let functions = GpioFunctions {
+            gpio0: Gpio0Function::PdoaSw,
+            gpio3: Gpio3Function::TxLed,
+            gpio4: Gpio4Function::Irq,
+            gpio8: Gpio8Function::Gpio,
+            ..Default::default()
+        };
+
+        let value = functions.to_register();
+        assert_eq!(value, 0x2 | 0x1 << 9 | 0x2 << 12 | 0x1 << 24);
+        assert_eq!(GpioFunctions::from_register(value), Some(functions));
+
+        // GPIO5 has no mode 2
+        assert_eq!(GpioFunctions::from_register(0x2 << 15), None);
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_gpio_pin() {
+        let spi = SpiMock::new(&[
+            // GPIO_MODE, all pins in mode 0
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4A, 0x00, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            // GPIO_OUT, GPIO2 is set
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4A, 0x30, 0, 0], vec![0, 0, 0x01, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x30, 0x05, 0x00]),
+            SpiTransaction::transaction_end(),
+            // GPIO_RAW
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4A, 0xB0, 0, 0], vec![0, 0, 0x04, 0x00]),
+            SpiTransaction::transaction_end(),
+            // GPIO7 is in SYNC mode
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4A, 0x00, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let mut pin = dw3000.gpio_pin(GpioPin::Gpio2).await.unwrap();
+        pin.set_high().await.unwrap();
+        let high = pin.is_high().await.unwrap();
+        assert!(high);
+
+        let result = dw3000.gpio_pin(GpioPin::Gpio7).await;
+        assert!(matches!(result, Err(Error::InvalidConfiguration)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -0,0 +1,226 @@
+//! Waiting for the IRQ line instead of polling (async only)
+
+use embedded_hal_async::digital::Wait;
+
+use super::{Events, Message, Receiving, RX_WAIT_EVENTS};
+use crate::{spi_type, time::Instant, Error, Sending, DW3000};
+
+impl<SPI> DW3000<SPI, Sending>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Waits for the transmission to finish
+    ///
+    /// Unlike `s_wait`, this doesn't poll the DW3000, but sleeps until `irq`,
+    /// the pin connected to the IRQ line, goes high. Overwrites the interrupt
+    /// mask.
+    ///
+    /// Returns the TX timestamp.
+    pub async fn wait_for_tx<IRQ>(&mut self, irq: &mut IRQ) -> Result<Instant, Error<SPI>>
+    where
+        IRQ: Wait,
+    {
+        self.set_interrupt_mask(Events::TXFRS | Events::HPDWARN)
+            .await?;
+
+        loop {
+            irq.wait_for_high().await.map_err(|_| Error::Irq)?;
+
+            match self.s_wait().await {
+                Ok(instant) => return Ok(instant),
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(error)) => return Err(error),
+            }
+        }
+    }
+}
+
+impl<SPI, RECEIVING> DW3000<SPI, RECEIVING>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    RECEIVING: Receiving,
+{
+    /// Waits for a frame to be received
+    ///
+    /// Unlike `r_wait`, this doesn't poll the DW3000, but sleeps until `irq`,
+    /// the pin connected to the IRQ line, goes high. Overwrites the interrupt
+    /// mask.
+    pub async fn wait_for_rx<'b, IRQ>(
+        &mut self,
+        irq: &mut IRQ,
+        buffer: &'b mut [u8],
+    ) -> Result<Message<'b>, Error<SPI>>
+    where
+        IRQ: Wait,
+    {
+        self.set_interrupt_mask(RX_WAIT_EVENTS).await?;
+
+        loop {
+            irq.wait_for_high().await.map_err(|_| Error::Irq)?;
+
+            // `r_wait` borrows the buffer for the lifetime of the message, so
+            // only hand it over once a frame or an error is pending
+            let events = self.read_events().await?;
+            if !events.intersects(RX_WAIT_EVENTS) {
+                continue;
+            }
+
+            return match self.r_wait(buffer).await {
+                Ok(message) => Ok(message),
+                Err(nb::Error::WouldBlock) => Err(Error::RxNotFinished),
+                Err(nb::Error::Other(error)) => {
+                    // Release the IRQ line, `r_wait` leaves errors pending
+                    self.clear_events(RX_WAIT_EVENTS).await?;
+                    Err(error)
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ll, Config, SingleBufferReceiving};
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::{
+        digital::{Mock as PinMock, State as PinState, Transaction as PinTransaction},
+        spi::{Mock as SpiMock, Transaction as SpiTransaction},
+        MockError,
+    };
+
+    #[tokio::test]
+    async fn test_wait_for_tx() {
+        let spi = SpiMock::new(&[
+            // Only TXFRS and HPDWARN assert the IRQ line
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xF0, 0x80, 0, 0, 0x08, 0, 0]),
+            SpiTransaction::transaction_end(),
+            // The frame hasn't been sent yet
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0, 0, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            // SYS_STATUS with TXFRS set
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0xF0, 0, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0xF0, 0, 0, 0x08, 0, 0]),
+            SpiTransaction::transaction_end(),
+            // TX_TIME
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0xD0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x10, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+        ]);
+        let mut irq = PinMock::new(&[
+            PinTransaction::wait_for_state(PinState::High),
+            PinTransaction::wait_for_state(PinState::High),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Sending { finished: false },
+        };
+
+        let tx_time = dw3000.wait_for_tx(&mut irq).await.unwrap();
+        assert_eq!(tx_time.value(), 0x1000);
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        irq.done();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_rx_error() {
+        let spi = SpiMock::new(&[
+            // Only the events checked by `r_wait` assert the IRQ line
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xF0, 0x00, 0xD0, 0x33, 0x24, 0, 0]),
+            SpiTransaction::transaction_end(),
+            // Only RXPRD is pending, keep waiting
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x01, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            // RXFCE is pending, which `r_wait` reports
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x81, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x81, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            // The RX events are cleared to release the IRQ line
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0x00, 0xD0, 0x33, 0x24, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+        let mut irq = PinMock::new(&[
+            PinTransaction::wait_for_state(PinState::High),
+            PinTransaction::wait_for_state(PinState::High),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: SingleBufferReceiving {
+                finished: false,
+                config: Config::default(),
+                sniff: false,
+            },
+        };
+
+        let mut buffer = [0; 128];
+        let result = dw3000.wait_for_rx(&mut irq, &mut buffer).await;
+        assert!(matches!(result, Err(Error::Fcs)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        irq.done();
+    }
+
+    #[tokio::test]
+    async fn test_irq_pin_error() {
+        let spi = SpiMock::new(&[
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xF0, 0x80, 0, 0, 0x08, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+        let mut irq = PinMock::new(&[PinTransaction::wait_for_state(PinState::High)
+            .with_error(MockError::Io(std::io::ErrorKind::Other))]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Sending { finished: false },
+        };
+
+        let result = dw3000.wait_for_tx(&mut irq).await;
+        assert!(matches!(result, Err(Error::Irq)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        irq.done();
+    }
+}
@@ -0,0 +1,198 @@
+//! Activity LEDs driven by the DW3000
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::{Gpio0Function, Gpio1Function, Gpio2Function, Gpio3Function, GpioFunction};
+use crate::{maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// Configuration of the activity LEDs on GPIO0 to GPIO3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct LedConfig {
+    /// How long an LED stays lit, in units of about 14 ms
+    pub blink_time: u8,
+    /// Blink the RXOK LED on GPIO0 on good frame reception
+    pub rx_ok: bool,
+    /// Blink the SFD LED on GPIO1 on SFD detection
+    pub sfd: bool,
+    /// Blink the RX LED on GPIO2 while the receiver is on
+    pub rx: bool,
+    /// Blink the TX LED on GPIO3 while transmitting
+    pub tx: bool,
+}
+
+impl Default for LedConfig {
+    fn default() -> Self {
+        LedConfig {
+            blink_time: 0x10,
+            rx_ok: true,
+            sfd: true,
+            rx: true,
+            tx: true,
+        }
+    }
+}
+
+impl LedConfig {
+    /// Mask of the RXOK LED for [`DW3000::blink_leds`]
+    pub const RX_OK: u8 = 0b0001;
+    /// Mask of the SFD LED for [`DW3000::blink_leds`]
+    pub const SFD: u8 = 0b0010;
+    /// Mask of the RX LED for [`DW3000::blink_leds`]
+    pub const RX: u8 = 0b0100;
+    /// Mask of the TX LED for [`DW3000::blink_leds`]
+    pub const TX: u8 = 0b1000;
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Configures the activity LEDs
+    ///
+    /// The pins of disabled LEDs are returned to GPIO mode. Enables the GPIO
+    /// and debounce clocks, which the blink timer runs on.
+    #[maybe_async_attr]
+    pub async fn configure_leds(&mut self, config: LedConfig) -> Result<(), Error<SPI>> {
+        let rx_ok = if config.rx_ok {
+            Gpio0Function::RxOkLed
+        } else {
+            Gpio0Function::Gpio
+        };
+        let sfd = if config.sfd {
+            Gpio1Function::SfdLed
+        } else {
+            Gpio1Function::Gpio
+        };
+        let rx = if config.rx {
+            Gpio2Function::RxLed
+        } else {
+            Gpio2Function::Gpio
+        };
+        let tx = if config.tx {
+            Gpio3Function::TxLed
+        } else {
+            Gpio3Function::Gpio
+        };
+
+        self.ll
+            .clk_ctrl()
+            .modify_masked(|w| {
+                w.gpio_clk_en(0b1)
+                    .gpio_dclk_en(0b1)
+                    .gpio_drst_n(0b1)
+                    .lp_clk_en(0b1)
+            })
+            .await?;
+
+        self.ll
+            .gpio_mode()
+            .modify(|_, w| {
+                w.msgp0(rx_ok.mode())
+                    .msgp1(sfd.mode())
+                    .msgp2(rx.mode())
+                    .msgp3(tx.mode())
+            })
+            .await?;
+
+        let enabled = config.rx_ok || config.sfd || config.rx || config.tx;
+        self.ll
+            .led_ctrl()
+            .write(|w| w.blink_tim(config.blink_time).blink_en(enabled as u8))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Blinks the LEDs in `mask` once
+    ///
+    /// `mask` is a combination of [`LedConfig::RX_OK`], [`LedConfig::SFD`],
+    /// [`LedConfig::RX`] and [`LedConfig::TX`]. The LEDs have to be enabled
+    /// with [`DW3000::configure_leds`].
+    #[maybe_async_attr]
+    pub async fn blink_leds(&mut self, mask: u8) -> Result<(), Error<SPI>> {
+        self.ll
+            .led_ctrl()
+            .modify(|_, w| w.force_trig(mask & 0x0F))
+            .await?;
+        self.ll.led_ctrl().modify(|_, w| w.force_trig(0)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_configure_and_blink_leds() {
+        let spi = SpiMock::new(&[
+            // GPIO, debounce and kilohertz clocks
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x19, 0xFF, 0x8D]),
+            SpiTransaction::transaction_end(),
+            // RXOK, RX and TX LEDs, GPIO1 stays a GPIO
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4A, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x20, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x00, 0x41, 0x02, 0x20, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Blink time and enable
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x58, 0x10, 0x01, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Trigger the RX and TX LEDs
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x62, 0x58, 0, 0, 0, 0],
+                vec![0, 0, 0x10, 0x01, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x58, 0x10, 0x01, 0x0C, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Release the trigger
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x62, 0x58, 0, 0, 0, 0],
+                vec![0, 0, 0x10, 0x01, 0x0C, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x58, 0x10, 0x01, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        dw3000
+            .configure_leds(LedConfig {
+                sfd: false,
+                ..LedConfig::default()
+            })
+            .await
+            .unwrap();
+        dw3000
+            .blink_leds(LedConfig::RX | LedConfig::TX)
+            .await
+            .unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
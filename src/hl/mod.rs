@@ -13,27 +13,59 @@ use core::{fmt, num::Wrapping};
 
 #[allow(unused_imports)]
 pub use awake::*;
+pub use clock::*;
+pub use compensation::*;
+pub use dual_spi::*;
+pub use dump::*;
 pub use error::*;
+pub use event_counters::*;
+pub use events::*;
+pub use front_end::*;
+pub use gpio::*;
+pub use led::*;
+pub use otp::*;
+pub use pdoa::*;
 pub use ready::*;
 #[allow(unused_imports)]
 pub use receiving::*;
+pub use sar::*;
 #[allow(unused_imports)]
 pub use sending::*;
 #[allow(unused_imports)]
 pub use sleeping::*;
+pub use sniff::*;
 pub use state_impls::*;
+pub use sync::*;
 #[allow(unused_imports)]
 pub use uninitialized::*;
 
 use crate::ll;
 
 mod awake;
+mod blocking;
+mod clock;
+mod compensation;
+mod dual_spi;
+mod dump;
 mod error;
+mod event_counters;
+mod events;
+mod front_end;
+mod gpio;
+#[cfg(feature = "async")]
+mod irq;
+mod led;
+mod otp;
+mod pdoa;
 mod ready;
 mod receiving;
+mod sar;
 mod sending;
 mod sleeping;
+mod sniff;
 mod state_impls;
+mod sync;
+mod test_mode;
 mod uninitialized;
 
 /// Entry point to the DW3000 driver API
@@ -0,0 +1,403 @@
+//! One-time programmable (OTP) memory
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+
+use super::Awake;
+use crate::{maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// The calibration data stored in OTP memory
+///
+/// Read with [`DW3000::read_otp_data`]. Fields that have not been programmed
+/// read as zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct OtpData {
+    /// The IEEE EUI-64 of the device
+    pub eui: u64,
+    /// The LDO tuning calibration value
+    pub ldo_tune: u64,
+    /// The part ID
+    pub part_id: u32,
+    /// The lot ID
+    pub lot_id: u32,
+    /// The SAR voltage reading taken at 3.0 V
+    pub vbat: u8,
+    /// The SAR temperature reading taken at 22 °C
+    pub vtemp: u8,
+    /// The bias tuning calibration value
+    pub bias_tune: u8,
+    /// The antenna delay calibration slot
+    pub antenna_delay: u32,
+    /// The TX power calibration slots, in the order channel 5 at 16 MHz and
+    /// 64 MHz PRF, then channel 9 at 16 MHz and 64 MHz PRF
+    pub tx_power: [u32; 4],
+    /// The crystal trim value
+    pub xtal_trim: u8,
+    /// The revision of the OTP memory layout
+    pub revision: u8,
+    /// The PLL lock code
+    pub pll_lock_code: u32,
+}
+
+impl OtpData {
+    /// Lower 32 bits of the EUI-64
+    pub const EUI_LOW_ADDRESS: u16 = 0x00;
+    /// Upper 32 bits of the EUI-64
+    pub const EUI_HIGH_ADDRESS: u16 = 0x01;
+    /// Lower 32 bits of the LDO tuning value
+    pub const LDO_TUNE_LOW_ADDRESS: u16 = 0x04;
+    /// Upper 32 bits of the LDO tuning value
+    pub const LDO_TUNE_HIGH_ADDRESS: u16 = 0x05;
+    /// Part ID
+    pub const PART_ID_ADDRESS: u16 = 0x06;
+    /// Lot ID
+    pub const LOT_ID_ADDRESS: u16 = 0x07;
+    /// SAR voltage reading at 3.0 V in bits 0-7
+    pub const VBAT_ADDRESS: u16 = 0x08;
+    /// SAR temperature reading at 22 °C in bits 0-7
+    pub const VTEMP_ADDRESS: u16 = 0x09;
+    /// Bias tuning value in bits 16-20
+    pub const BIAS_TUNE_ADDRESS: u16 = 0x0A;
+    /// First of the four TX power calibration slots
+    pub const TX_POWER_ADDRESS: u16 = 0x10;
+    /// Antenna delay calibration slot
+    pub const ANTENNA_DELAY_ADDRESS: u16 = 0x1C;
+    /// Crystal trim in bits 0-5
+    pub const XTAL_TRIM_ADDRESS: u16 = 0x1E;
+    /// OTP revision in bits 0-7
+    pub const REVISION_ADDRESS: u16 = 0x1F;
+    /// First of the RX tuning (DGC) configuration words
+    pub const DGC_ADDRESS: u16 = 0x20;
+    /// PLL lock code
+    pub const PLL_LOCK_CODE_ADDRESS: u16 = 0x35;
+}
+
+/// OTP mode register value that selects fast programming
+const OTP_MR_FAST_PROGRAMMING: u32 = 0x0025;
+
+/// Interval at which `OTP_STAT` is polled while programming, in µs
+///
+/// Qorvo's API sleeps 1 ms before checking whether programming is done.
+const OTP_PROG_POLL_INTERVAL_US: u32 = 1000;
+
+/// Time after which programming a word is abandoned, in µs
+const OTP_PROG_TIMEOUT_US: u32 = 10_000;
+
+/// The operating parameter sets stored in OTP
+///
+/// The OTP controller loads the selected set into the receiver
+/// configuration, see [`DW3000::load_ops_parameter_set`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+#[repr(u8)]
+pub enum OpsParameterSet {
+    /// For preambles longer than 256 symbols
+    Long = 0,
+    /// For the super-chirp preamble (SCP) mode
+    Scp = 1,
+    /// For preambles of up to 256 symbols
+    Short = 2,
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Reads and decodes the calibration data stored in OTP memory
+    #[maybe_async_attr]
+    pub async fn read_otp_data(&mut self) -> Result<OtpData, Error<SPI>> {
+        let eui_low = self.read_otp(OtpData::EUI_LOW_ADDRESS).await?;
+        let eui_high = self.read_otp(OtpData::EUI_HIGH_ADDRESS).await?;
+        let ldo_tune_low = self.read_otp(OtpData::LDO_TUNE_LOW_ADDRESS).await?;
+        let ldo_tune_high = self.read_otp(OtpData::LDO_TUNE_HIGH_ADDRESS).await?;
+
+        let mut tx_power = [0; 4];
+        for (address, slot) in (OtpData::TX_POWER_ADDRESS..).zip(tx_power.iter_mut()) {
+            *slot = self.read_otp(address).await?;
+        }
+
+        Ok(OtpData {
+            eui: (eui_high as u64) << 32 | eui_low as u64,
+            ldo_tune: (ldo_tune_high as u64) << 32 | ldo_tune_low as u64,
+            part_id: self.read_otp(OtpData::PART_ID_ADDRESS).await?,
+            lot_id: self.read_otp(OtpData::LOT_ID_ADDRESS).await?,
+            vbat: self.read_otp(OtpData::VBAT_ADDRESS).await? as u8,
+            vtemp: self.read_otp(OtpData::VTEMP_ADDRESS).await? as u8,
+            bias_tune: (self.read_otp(OtpData::BIAS_TUNE_ADDRESS).await? >> 16 & 0x1F) as u8,
+            antenna_delay: self.read_otp(OtpData::ANTENNA_DELAY_ADDRESS).await?,
+            tx_power,
+            xtal_trim: (self.read_otp(OtpData::XTAL_TRIM_ADDRESS).await? & 0x3F) as u8,
+            revision: self.read_otp(OtpData::REVISION_ADDRESS).await? as u8,
+            pll_lock_code: self.read_otp(OtpData::PLL_LOCK_CODE_ADDRESS).await?,
+        })
+    }
+
+    /// Loads an operating parameter set from OTP
+    #[maybe_async_attr]
+    pub async fn load_ops_parameter_set(&mut self, set: OpsParameterSet) -> Result<(), Error<SPI>> {
+        self.ll
+            .otp_cfg()
+            .modify(|_, w| w.ops_sel(set as u8).ops_kick(1))
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Programs a 32-bit word of OTP memory
+    ///
+    /// OTP memory can only be programmed once: bits can be set, but never
+    /// cleared again. Follows `_dwt_otpprogword32` of Qorvo's DW3000 API: the
+    /// HVAUX LDO is raised to its maximum to supply the programming voltage,
+    /// the OTP mode register is set to fast programming, and the word is
+    /// programmed. Afterwards the status is cleared and the LDO restored.
+    /// `delay` is used to wait between polls of the programming status.
+    ///
+    /// Returns [`Error::InvalidConfiguration`] if `value` would need to clear
+    /// bits that are already set, [`Error::Timeout`] if programming doesn't
+    /// finish, and [`Error::OtpVerificationFailed`] if the word reads back a
+    /// different value afterwards.
+    #[maybe_async_attr]
+    pub async fn program_otp<DELAY>(
+        &mut self,
+        addr: u16,
+        value: u32,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let current = self.read_otp(addr).await?;
+        if current == value {
+            return Ok(());
+        }
+        if current & !value != 0 {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        // Raise the HVAUX LDO, which supplies the programming voltage
+        let ldo_tune = self.ll.ldo_tune_hi().read().await?.value();
+        self.ll
+            .ldo_tune_hi()
+            .modify_masked(|w| w.ldo_hvaux_tune(0xF))
+            .await?;
+
+        // Select fast programming in the mode register
+        self.ll
+            .otp_wdata()
+            .write(|w| w.value(OTP_MR_FAST_PROGRAMMING))
+            .await?;
+        self.ll
+            .otp_cfg()
+            .write(|w| w.otp_man(1).otp_write_mr(1))
+            .await?;
+        self.ll.otp_cfg().write(|w| w.otp_man(1)).await?;
+
+        // Program the word
+        self.ll.otp_wdata().write(|w| w.value(value)).await?;
+        self.ll.otp_addr().write(|w| w.otp_addr(addr)).await?;
+        self.ll
+            .otp_cfg()
+            .write(|w| w.otp_man(1).otp_write(1))
+            .await?;
+
+        let mut done = false;
+        let mut remaining = OTP_PROG_TIMEOUT_US;
+        while remaining > 0 {
+            delay.delay_us(OTP_PROG_POLL_INTERVAL_US).await;
+            remaining = remaining.saturating_sub(OTP_PROG_POLL_INTERVAL_US);
+
+            if self.ll.otp_stat().read().await?.otp_prog_done() == 1 {
+                done = true;
+                break;
+            }
+        }
+
+        // Leave manual mode, clear the status and restore the LDO
+        self.ll.otp_cfg().write(|w| w).await?;
+        self.ll.otp_stat().write(|w| w.otp_prog_done(1)).await?;
+        self.ll.ldo_tune_hi().write(|w| w.value(ldo_tune)).await?;
+
+        if !done {
+            return Err(Error::Timeout);
+        }
+
+        if self.read_otp(addr).await? != value {
+            return Err(Error::OtpVerificationFailed);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::{
+        delay::{CheckedDelay, Transaction as DelayTransaction},
+        spi::{Mock as SpiMock, Transaction as SpiTransaction},
+    };
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_program_otp() {
+        let expectations = [
+            // Read the current XTAL_TRIM word
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x01, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x56, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x10, 0x1E, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x02, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x56, 0x40, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            // Save LDO_TUNE_HI and raise the HVAUX LDO
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4F, 0x10, 0, 0, 0, 0],
+                vec![0, 0, 0x78, 0x56, 0x34, 0x12],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCF, 0x15, 0xFF, 0xF0]),
+            SpiTransaction::transaction_end(),
+            // Write the mode register
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x00, 0x25, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x09, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x01, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Program the word
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x00, 0x21, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x10, 0x1E, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x05, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x56, 0x30, 0], vec![0; 3]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x56, 0x30, 0], vec![0, 0, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Clean up and restore LDO_TUNE_HI
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x30, 0x01]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCF, 0x10, 0x78, 0x56, 0x34, 0x12]),
+            SpiTransaction::transaction_end(),
+            // Verify
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x01, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x56, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x10, 0x1E, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x02, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x56, 0x40, 0, 0, 0, 0],
+                vec![0, 0, 0x21, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+        ];
+
+        let mut delay = CheckedDelay::new(&[
+            DelayTransaction::delay_us(OTP_PROG_POLL_INTERVAL_US),
+            DelayTransaction::delay_us(OTP_PROG_POLL_INTERVAL_US),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(SpiMock::new(&expectations)),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        dw3000
+            .program_otp(OtpData::XTAL_TRIM_ADDRESS, 0x21, &mut delay)
+            .await
+            .unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        delay.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_program_otp_refuses_to_clear_bits() {
+        let expectations = [
+            // Read the current XTAL_TRIM word
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x01, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x56, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x10, 0x1E, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD6, 0x20, 0x02, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x56, 0x40, 0, 0, 0, 0],
+                vec![0, 0, 0x21, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+        ];
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(SpiMock::new(&expectations)),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let mut delay = CheckedDelay::new(&[]);
+        let result = dw3000
+            .program_otp(OtpData::XTAL_TRIM_ADDRESS, 0x20, &mut delay)
+            .await;
+        assert!(matches!(result, Err(Error::InvalidConfiguration)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+        delay.done();
+    }
+}
@@ -0,0 +1,202 @@
+//! Phase Difference of Arrival (PDoA) and Angle of Arrival (AoA) helpers
+
+#[cfg(any(feature = "aoa", test))]
+use core::f32::consts::PI;
+
+use fixed::types::I5F11;
+#[cfg(all(feature = "aoa", not(any(test, feature = "std"))))]
+use num_traits::Float;
+
+#[cfg(feature = "aoa")]
+use crate::configs::UwbChannel;
+use crate::{maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// The phase difference measured between the two antennas of a PDoA capable
+/// device
+///
+/// Only available if the DW3000 was configured with a PDoA mode other than
+/// [`PdoaMode::Mode0`].
+///
+/// [`PdoaMode::Mode0`]: crate::configs::PdoaMode::Mode0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pdoa {
+    /// The phase difference in radians
+    ///
+    /// This is the raw value of the `PDOA` register, a signed fixed-point
+    /// number in units of 2^-11 radians. The value lies within -π and π.
+    pub phase_difference: I5F11,
+    /// First path threshold test mode
+    ///
+    /// Set when the first path of the two CIRs did not agree, and the CIA
+    /// fell back to a less strict threshold to find it.
+    pub fp_th_md: bool,
+}
+
+impl Pdoa {
+    /// Builds a `Pdoa` from the fields of the `PDOA` register
+    pub(crate) fn from_register(pdoa: u16, fp_th_md: u8) -> Self {
+        // The phase difference is a 14-bit two's complement number
+        let raw = ((pdoa << 2) as i16) >> 2;
+
+        Pdoa {
+            phase_difference: I5F11::from_bits(raw),
+            fp_th_md: fp_th_md != 0,
+        }
+    }
+
+    /// Returns the phase difference in radians
+    pub fn radians(&self) -> f32 {
+        self.phase_difference.to_num()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Pdoa {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Pdoa {{ phase_difference: {} rad, fp_th_md: {} }}",
+            self.radians(),
+            self.fp_th_md
+        );
+    }
+}
+
+/// Converts PDoA measurements into an azimuth (angle of arrival)
+///
+/// The azimuth is the angle between the direction of the incoming signal and
+/// the normal of the line connecting the two antennas. It is computed as
+///
+/// `azimuth = asin(pdoa * wavelength / (2 * π * antenna_spacing))`
+///
+/// Unbalanced antenna circuits add a constant phase offset to every
+/// measurement. Use [`AoaEstimator::calibrate`] with a measurement taken at a
+/// known angle to determine it, then write it to the device with
+/// [`DW3000::set_pdoa_offset`], which makes the DW3000 subtract it from every
+/// PDoA result.
+#[cfg(feature = "aoa")]
+#[derive(Clone, Copy, Debug)]
+pub struct AoaEstimator {
+    antenna_spacing: f32,
+    wavelength: f32,
+    phase_offset: I5F11,
+}
+
+#[cfg(feature = "aoa")]
+impl AoaEstimator {
+    /// Creates a new estimator
+    ///
+    /// `antenna_spacing` is the distance between the two antennas in meters.
+    /// For unambiguous results it must not be larger than half the wavelength
+    /// of `channel`.
+    pub fn new(antenna_spacing: f32, channel: UwbChannel) -> Self {
+        AoaEstimator {
+            antenna_spacing,
+            wavelength: channel.get_wavelength(),
+            phase_offset: I5F11::ZERO,
+        }
+    }
+
+    /// Creates a new estimator with a previously calibrated phase offset
+    pub fn with_phase_offset(mut self, phase_offset: I5F11) -> Self {
+        self.phase_offset = phase_offset;
+        self
+    }
+
+    /// Returns the calibrated phase offset
+    pub fn phase_offset(&self) -> I5F11 {
+        self.phase_offset
+    }
+
+    /// Determines the phase offset of the device
+    ///
+    /// `pdoa` has to be measured with a source placed at the known `azimuth`
+    /// (in radians), and with the `CIA_ADJUST` register cleared, so that the
+    /// raw phase difference is reported.
+    pub fn calibrate(&mut self, pdoa: &Pdoa, azimuth: f32) -> I5F11 {
+        let expected = 2.0 * PI * self.antenna_spacing * azimuth.sin() / self.wavelength;
+
+        let mut offset = pdoa.radians() - expected;
+        if offset > PI {
+            offset -= 2.0 * PI;
+        } else if offset < -PI {
+            offset += 2.0 * PI;
+        }
+
+        self.phase_offset = I5F11::saturating_from_num(offset);
+        self.phase_offset
+    }
+
+    /// Computes the azimuth in radians from a PDoA measurement
+    ///
+    /// The measurement is expected to already be corrected by the DW3000,
+    /// i.e. the phase offset has been written with
+    /// [`DW3000::set_pdoa_offset`].
+    pub fn azimuth(&self, pdoa: &Pdoa) -> f32 {
+        let sin = pdoa.radians() * self.wavelength / (2.0 * PI * self.antenna_spacing);
+
+        sin.clamp(-1.0, 1.0).asin()
+    }
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Sets the phase offset the DW3000 subtracts from every PDoA result
+    ///
+    /// The offset compensates for non-balanced antenna circuits and is written
+    /// to the `CIA_ADJUST` register.
+    #[maybe_async_attr]
+    pub async fn set_pdoa_offset(&mut self, offset: I5F11) -> Result<(), Error<SPI>> {
+        self.ll
+            .cia_adjust()
+            .modify(|_, w| w.value(offset.to_bits() as u16 & 0x3FFF))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "aoa")]
+    use crate::configs::UwbChannel;
+
+    #[test]
+    fn pdoa_sign_extension() {
+        // -1 in units of 2^-11
+        let pdoa = Pdoa::from_register(0x3FFF, 0);
+        assert_eq!(pdoa.phase_difference.to_bits(), -1);
+
+        // π/2
+        let pdoa = Pdoa::from_register(0x0C91, 1);
+        assert!((pdoa.radians() - PI / 2.0).abs() < 1e-3);
+        assert!(pdoa.fp_th_md);
+    }
+
+    #[cfg(feature = "aoa")]
+    #[test]
+    fn aoa_roundtrip() {
+        let channel = UwbChannel::Channel5;
+        let spacing = channel.get_wavelength() / 2.0;
+        let mut estimator = AoaEstimator::new(spacing, channel);
+
+        // Broadside source with 0.5 rad of antenna imbalance
+        let measured = Pdoa {
+            phase_difference: I5F11::from_num(0.5),
+            fp_th_md: false,
+        };
+        let offset = estimator.calibrate(&measured, 0.0);
+        assert!((offset.to_num::<f32>() - 0.5).abs() < 1e-3);
+
+        // With half a wavelength spacing, a phase difference of π/2 is 30°
+        let corrected = Pdoa {
+            phase_difference: I5F11::from_num(PI / 2.0),
+            fp_th_md: false,
+        };
+        let azimuth = estimator.azimuth(&corrected);
+        assert!((azimuth - PI / 6.0).abs() < 1e-3);
+    }
+}
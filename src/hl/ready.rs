@@ -4,7 +4,7 @@ use core::{num::Wrapping, ops::Not};
 
 use byte::BytesExt as _;
 
-use super::AutoDoubleBufferReceiving;
+use super::{AutoDoubleBufferReceiving, SYNC_WAIT};
 use crate::{
     configs::{PdoaMode, SfdSequence},
     maybe_async_attr, spi_type,
@@ -15,6 +15,9 @@ use crate::{
 use smoltcp::wire::{Ieee802154Address, Ieee802154Frame, Ieee802154Pan, Ieee802154Repr};
 
 /// The behaviour of the sync pin
+///
+/// Configured with [`DW3000::set_sync_behaviour`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SyncBehaviour {
     /// The sync pin does nothing
     None,
@@ -23,6 +26,10 @@ pub enum SyncBehaviour {
     TimeBaseReset,
     /// When receiving, instead of reading the internal timestamp, the time
     /// since the last sync is given back.
+    ///
+    /// The DW3000 has no separate counter for the external clock, the time
+    /// since the last sync is measured by resetting the timebase. This is
+    /// the same as `ExternalSyncWithReset`.
     ExternalSync,
     /// When receiving, instead of reading the internal timestamp, the time
     /// since the last sync is given back. Also resets the internal timebase
@@ -36,8 +43,14 @@ pub enum SendTime {
     Now,
     /// After some time
     Delayed(Instant),
-    /// After the sync pin is engaged. (Only works when sync setup is in
-    /// ExternalSync mode)
+    /// After the sync pin is engaged
+    ///
+    /// The DW3000 can't start a transmission on the SYNC pulse directly.
+    /// Instead, the pulse resets the system time `OSTS_WAIT` cycles later
+    /// (one-shot timebase reset), and a delayed transmission at time 0
+    /// starts as the time is reset. The SYNC pin has to be configured with
+    /// [`DW3000::set_sync_behaviour`]. Without a pulse, the frame is sent
+    /// once the system time wraps around.
     OnSync,
 }
 
@@ -53,6 +66,18 @@ impl<SPI> DW3000<SPI, Ready>
 where
     SPI: spi_type::spi::SpiDevice<u8>,
 {
+    /// Clears (if configured) and enables the event counters before sending
+    #[maybe_async_attr]
+    async fn prepare_event_counters(&mut self, config: &Config) -> Result<(), Error<SPI>> {
+        if config.clear_event_counters {
+            self.reset_event_counters().await?;
+        } else {
+            self.ll.evc_ctrl().write(|w| w.evc_en(0b1)).await?;
+        }
+
+        Ok(())
+    }
+
     /// Sets the RX and TX antenna delays
     #[maybe_async_attr]
     pub async fn set_antenna_delay(
@@ -66,23 +91,69 @@ where
         Ok(())
     }
 
+    /// Sets the crystal trim
+    ///
+    /// The trim value adjusts the load capacitance of the crystal and thereby
+    /// its frequency. Higher values lower the frequency. Valid values are
+    /// `0..=0x3F`, the default is the value programmed in OTP, or `0x2E` if
+    /// the OTP is empty.
+    ///
+    /// See [`XtalTrimCalibrator`] to determine the value.
+    ///
+    /// [`XtalTrimCalibrator`]: crate::hl::XtalTrimCalibrator
+    #[maybe_async_attr]
+    pub async fn set_xtal_trim(&mut self, trim: u8) -> Result<(), Error<SPI>> {
+        if trim > 0x3F {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        self.ll.xtal().modify(|_, w| w.value(trim)).await?;
+
+        Ok(())
+    }
+
     /// Sets the network id and address used for sending and receiving
+    ///
+    /// If `addr` is an extended address, it replaces the EUI and the short
+    /// address is set to `0xFFFE`, so that frames are sent and filtered with
+    /// the extended address only.
     #[maybe_async_attr]
     pub async fn set_address(
         &mut self,
         pan_id: Ieee802154Pan,
         addr: Ieee802154Address,
     ) -> Result<(), Error<SPI>> {
-        let Ieee802154Address::Short(short_addr) = addr else {
-            return Err(Error::InvalidConfiguration);
+        let short_addr = match addr {
+            Ieee802154Address::Short(short_addr) => u16::from_be_bytes(short_addr),
+            Ieee802154Address::Extended(_) => {
+                self.set_eui(addr).await?;
+                0xFFFE
+            }
+            Ieee802154Address::Absent => return Err(Error::InvalidConfiguration),
         };
 
         self.ll
             .panadr()
-            .write(|w| {
-                w.pan_id(pan_id.0)
-                    .short_addr(u16::from_be_bytes(short_addr))
-            })
+            .write(|w| w.pan_id(pan_id.0).short_addr(short_addr))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the extended address (EUI-64)
+    ///
+    /// The DW3000 uses it to filter frames sent to an extended destination
+    /// address, and `send` uses it as source address when no short address is
+    /// assigned.
+    #[maybe_async_attr]
+    pub async fn set_eui(&mut self, eui: Ieee802154Address) -> Result<(), Error<SPI>> {
+        let Ieee802154Address::Extended(eui) = eui else {
+            return Err(Error::InvalidConfiguration);
+        };
+
+        self.ll
+            .eui()
+            .write(|w| w.value(u64::from_be_bytes(eui)))
             .await?;
 
         Ok(())
@@ -116,7 +187,7 @@ where
     pub async fn set_pdoa_mode(&mut self, mode: PdoaMode) -> Result<(), Error<SPI>> {
         self.ll
             .sys_cfg()
-            .modify(|_, w| w.pdoa_mode(mode as u8))
+            .modify_masked(|w| w.pdoa_mode(mode as u8))
             .await?;
 
         Ok(())
@@ -148,76 +219,13 @@ where
         send_time: SendTime,
         config: &Config,
     ) -> Result<DW3000<SPI, Sending>, Error<SPI>> {
-        // Clear event counters
-        self.ll.evc_ctrl().write(|w| w.evc_clr(0b1)).await?;
-        // while self.ll.evc_ctrl().read()?.evc_clr() == 0b1 {}
-
-        // (Re-)Enable event counters
-        self.ll.evc_ctrl().write(|w| w.evc_en(0b1)).await?;
-        // while self.ll.evc_ctrl().read()?.evc_en() == 0b1 {}
+        self.prepare_event_counters(config).await?;
 
         // self.ll.clk_ctrl().modify(|_, w| w.tx_clk(0b10)).await?;
 
-        // Prepare transmitter
-        let mut len: usize = 0;
-        self.ll
-            .tx_buffer()
-            .write(|w| {
-                let result = w.data().write(&mut len, data);
-
-                if let Err(err) = result {
-                    panic!("Failed to write frame: {:?}", err);
-                }
-
-                w
-            })
-            .await?;
-
-        let txb_offset = 0; // no offset in TX_BUFFER
-        let mut txb_offset_errata = txb_offset;
-        if txb_offset > 127 {
-            // Errata in DW3000, see page 86
-            txb_offset_errata += 128;
-        }
-
-        self.ll
-            .tx_fctrl()
-            .modify(|_, w| {
-                let txflen = len as u16 + 2;
-                w.txflen(txflen) // data length + two-octet CRC
-                    .txbr(config.bitrate as u8) // configured bitrate
-                    .tr(config.ranging_enable as u8) // configured ranging bit
-                    .txb_offset(txb_offset_errata) // no offset in TX_BUFFER
-                    .txpsr(config.preamble_length as u8) // configure preamble length
-                    .fine_plen(0) // Not implemented, replacing txpsr
-            })
-            .await?;
-
-        match send_time {
-            SendTime::Delayed(time) => {
-                // Panic if the time is not rounded to top 31 bits
-                //
-                // NOTE: DW3000's DX_TIME register is 32 bits wide, but only the top 31 bits are used.
-                // The last bit is ignored per the user manual!!!
-                if time.value() % (1 << 9) != 0 {
-                    panic!("Time must be rounded to top 31 bits!");
-                }
+        self.write_tx_frame(data, config).await?;
 
-                // Put the time into the delay register
-                // By setting this register, the chip knows to delay before transmitting
-                self.ll
-                    .dx_time()
-                    .modify(|_, w| // 32-bits value of the most significant bits
-                    w.value( (time.value() >> 8) as u32 ))
-                    .await?;
-                self.fast_cmd(FastCommand::CMD_DTX).await?;
-            }
-            SendTime::OnSync => {
-                self.ll.ec_ctrl().modify(|_, w| w.ostr_mode(1)).await?;
-                self.ll.ec_ctrl().modify(|_, w| w.osts_wait(33)).await?;
-            }
-            SendTime::Now => self.fast_cmd(FastCommand::CMD_TX).await?,
-        }
+        self.start_transmission(send_time).await?;
 
         Ok(DW3000 {
             ll: self.ll,
@@ -254,15 +262,9 @@ where
     where
         T: AsRef<[u8]>,
     {
-        // Clear event counters
-        self.ll.evc_ctrl().write(|w| w.evc_clr(0b1)).await?;
-        while self.ll.evc_ctrl().read().await?.evc_clr() == 0b1 {}
+        self.prepare_event_counters(&config).await?;
 
-        // (Re-)Enable event counters
-        self.ll.evc_ctrl().write(|w| w.evc_en(0b1)).await?;
-        while self.ll.evc_ctrl().read().await?.evc_en() == 0b1 {}
-
-        self.ll.clk_ctrl().modify(|_, w| w.tx_clk(0b10)).await?;
+        self.ll.clk_ctrl().modify_masked(|w| w.tx_clk(0b10)).await?;
 
         // Prepare transmitter
         let buf = frame.into_inner();
@@ -298,28 +300,7 @@ where
             })
             .await?;
 
-        match send_time {
-            SendTime::Delayed(time) => {
-                // Panic if the time is not rounded to top 31 bits
-                if time.value() % (1 << 9) != 0 {
-                    panic!("Time must be rounded to top 31 bits!");
-                }
-
-                // Put the time into the delay register
-                // By setting this register, the chip knows to delay before transmitting
-                self.ll
-                    .dx_time()
-                    .modify(|_, w| // 32-bits value of the most significant bits
-                    w.value( (time.value() >> 8) as u32 ))
-                    .await?;
-                self.fast_cmd(FastCommand::CMD_DTX).await?;
-            }
-            SendTime::OnSync => {
-                self.ll.ec_ctrl().modify(|_, w| w.ostr_mode(1)).await?;
-                self.ll.ec_ctrl().modify(|_, w| w.osts_wait(33)).await?;
-            }
-            SendTime::Now => self.fast_cmd(FastCommand::CMD_TX).await?,
-        }
+        self.start_transmission(send_time).await?;
 
         Ok(DW3000 {
             ll: self.ll,
@@ -354,19 +335,32 @@ where
         send_time: SendTime,
         config: Config,
     ) -> Result<DW3000<SPI, Sending>, Error<SPI>> {
-        // Clear event counters
-        self.ll.evc_ctrl().write(|w| w.evc_clr(0b1)).await?;
-        while self.ll.evc_ctrl().read().await?.evc_clr() == 0b1 {}
+        self.start_send(data, send_time, &config).await?;
 
-        // (Re-)Enable event counters
-        self.ll.evc_ctrl().write(|w| w.evc_en(0b1)).await?;
-        while self.ll.evc_ctrl().read().await?.evc_en() == 0b1 {}
+        Ok(DW3000 {
+            ll: self.ll,
+            seq: self.seq,
+            state: Sending { finished: false },
+        })
+    }
 
-        self.ll.clk_ctrl().modify(|_, w| w.tx_clk(0b10)).await?;
+    /// Wraps `data` into a MAC frame and starts the transmission
+    #[maybe_async_attr]
+    pub(super) async fn start_send(
+        &mut self,
+        data: &[u8],
+        send_time: SendTime,
+        config: &Config,
+    ) -> Result<(), Error<SPI>> {
+        self.prepare_event_counters(config).await?;
+
+        self.ll.clk_ctrl().modify_masked(|w| w.tx_clk(0b10)).await?;
 
         let seq = self.seq.0;
         self.seq += Wrapping(1);
 
+        let (src_pan_id, src_addr) = self.get_address().await?;
+
         let frame_repr = Ieee802154Repr {
             frame_type: smoltcp::wire::Ieee802154FrameType::Data,
             frame_version: smoltcp::wire::Ieee802154FrameVersion::Ieee802154_2006,
@@ -376,8 +370,8 @@ where
             ack_request: false,
             pan_id_compression: true,
             dst_addr: Some(Ieee802154Address::BROADCAST),
-            src_addr: Some(self.get_address().await?.1),
-            src_pan_id: Some(self.get_address().await?.0),
+            src_addr: Some(src_addr),
+            src_pan_id: Some(src_pan_id),
             dst_pan_id: None,
         };
 
@@ -424,9 +418,69 @@ where
             })
             .await?;
 
+        self.start_transmission(send_time).await?;
+
+        Ok(())
+    }
+
+    /// Writes `data` to the TX buffer and configures the frame length
+    ///
+    /// Will panic if `data` doesn't fit into the TX buffer.
+    #[maybe_async_attr]
+    pub(super) async fn write_tx_frame(
+        &mut self,
+        data: &[u8],
+        config: &Config,
+    ) -> Result<(), Error<SPI>> {
+        // Prepare transmitter
+        let mut len: usize = 0;
+        self.ll
+            .tx_buffer()
+            .write(|w| {
+                let result = w.data().write(&mut len, data);
+
+                if let Err(err) = result {
+                    panic!("Failed to write frame: {:?}", err);
+                }
+
+                w
+            })
+            .await?;
+
+        let txb_offset = 0; // no offset in TX_BUFFER
+        let mut txb_offset_errata = txb_offset;
+        if txb_offset > 127 {
+            // Errata in DW3000, see page 86
+            txb_offset_errata += 128;
+        }
+
+        self.ll
+            .tx_fctrl()
+            .modify(|_, w| {
+                let txflen = len as u16 + 2;
+                w.txflen(txflen) // data length + two-octet CRC
+                    .txbr(config.bitrate as u8) // configured bitrate
+                    .tr(config.ranging_enable as u8) // configured ranging bit
+                    .txb_offset(txb_offset_errata) // no offset in TX_BUFFER
+                    .txpsr(config.preamble_length as u8) // configure preamble length
+                    .fine_plen(0) // Not implemented, replacing txpsr
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Starts the transmission that has been prepared in the TX buffer
+    ///
+    /// Will panic if the delayed TX time is not rounded to top 31 bits.
+    #[maybe_async_attr]
+    async fn start_transmission(&mut self, send_time: SendTime) -> Result<(), Error<SPI>> {
         match send_time {
             SendTime::Delayed(time) => {
                 // Panic if the time is not rounded to top 31 bits
+                //
+                // NOTE: DW3000's DX_TIME register is 32 bits wide, but only the top 31 bits are used.
+                // The last bit is ignored per the user manual!!!
                 if time.value() % (1 << 9) != 0 {
                     panic!("Time must be rounded to top 31 bits!");
                 }
@@ -441,17 +495,23 @@ where
                 self.fast_cmd(FastCommand::CMD_DTX).await?;
             }
             SendTime::OnSync => {
-                self.ll.ec_ctrl().modify(|_, w| w.ostr_mode(1)).await?;
-                self.ll.ec_ctrl().modify(|_, w| w.osts_wait(33)).await?;
+                // The next SYNC pulse resets the system time to 0, which
+                // starts the transmission
+                self.ll
+                    .ec_ctrl()
+                    .modify_masked(|w| w.ostr_mode(1).osts_wait(SYNC_WAIT))
+                    .await?;
+                self.ll.dx_time().write(|w| w.value(0)).await?;
+                self.fast_cmd(FastCommand::CMD_DTX).await?;
+
+                // Time 0 is usually less than half a period in the past, which
+                // isn't late here, as the SYNC pulse resets the time
+                self.ll.sys_status().write(|w| w.hpdwarn(0b1)).await?;
             }
             SendTime::Now => self.fast_cmd(FastCommand::CMD_TX).await?,
         }
 
-        Ok(DW3000 {
-            ll: self.ll,
-            seq: self.seq,
-            state: Sending { finished: false },
-        })
+        Ok(())
     }
 
     /// Attempt to receive a single IEEE 802.15.4 MAC frame
@@ -498,6 +558,7 @@ where
             state: SingleBufferReceiving {
                 finished: false,
                 config,
+                sniff: false,
             },
         };
 
@@ -511,7 +572,10 @@ where
     /// Disable the SPIRDY interrupt flag
     #[maybe_async_attr]
     pub async fn disable_spirdy_interrupt(&mut self) -> Result<(), Error<SPI>> {
-        self.ll.sys_enable().modify(|_, w| w.spirdy_en(0b0)).await?;
+        self.ll
+            .sys_enable()
+            .modify_masked(|w| w.spirdy_en(0b0))
+            .await?;
         Ok(())
     }
 
@@ -520,7 +584,10 @@ where
     /// Overwrites any interrupt flags that were previously set.
     #[maybe_async_attr]
     pub async fn enable_tx_interrupts(&mut self) -> Result<(), Error<SPI>> {
-        self.ll.sys_enable().modify(|_, w| w.txfrs_en(0b1)).await?;
+        self.ll
+            .sys_enable()
+            .modify_masked(|w| w.txfrs_en(0b1).hpdwarn_en(0b1))
+            .await?;
         Ok(())
     }
 
@@ -531,7 +598,7 @@ where
     pub async fn enable_rx_interrupts(&mut self) -> Result<(), Error<SPI>> {
         self.ll()
             .sys_enable()
-            .modify(|_, w| {
+            .modify_masked(|w| {
                 w
                     // .rxprd_en(0b1)
                     //     .rxsfdd_en(0b1)
@@ -557,373 +624,124 @@ where
         self.ll.sys_enable().write(|w| w).await?;
         Ok(())
     }
+}
 
-    /// GPIO SECTION, gpios seems to have a problem with its register.
-    /// Init GPIO WRT Config
-    #[maybe_async_attr]
-    pub async fn gpio_config(&mut self, config: ConfigGPIOs) -> Result<(), Error<SPI>> {
-        self.gpio_config_clocks().await?;
-
-        self.ll
-            .gpio_pull_en()
-            .modify(|_, w| {
-                w.mgpen0(config.enabled[0])
-                    .mgpen1(config.enabled[1])
-                    .mgpen2(config.enabled[2])
-                    .mgpen3(config.enabled[3])
-                    .mgpen4(config.enabled[4])
-                    .mgpen5(config.enabled[5])
-                    .mgpen6(config.enabled[6])
-                    .mgpen7(config.enabled[7])
-                    .mgpen8(config.enabled[8])
-            })
-            .await?;
-
-        self.ll
-            .gpio_mode()
-            .modify(|_, w| {
-                w.msgp0(0x0)
-                    .msgp1(0x0)
-                    .msgp2(0x0)
-                    .msgp3(0x0)
-                    .msgp4(0x0)
-                    .msgp5(0x0)
-                    .msgp6(0x0)
-                    .msgp7(0x0)
-                    .msgp8(0x0)
-            })
-            .await?;
-        self.ll
-            .gpio_mode()
-            .modify(|_, w| {
-                w.msgp0(config.mode[0])
-                    .msgp1(config.mode[1])
-                    .msgp2(config.mode[2])
-                    .msgp3(config.mode[3])
-                    .msgp4(config.mode[4])
-                    .msgp5(config.mode[5])
-                    .msgp6(config.mode[6])
-                    .msgp7(config.mode[7])
-                    .msgp8(config.mode[8])
-            })
-            .await?;
-
-        self.ll
-            .gpio_dir()
-            .modify(|_, w| {
-                w.gpd0(config.gpio_dir[0])
-                    .gpd1(config.gpio_dir[1])
-                    .gpd2(config.gpio_dir[2])
-                    .gpd3(config.gpio_dir[3])
-                    .gpd4(config.gpio_dir[4])
-                    .gpd5(config.gpio_dir[5])
-                    .gpd6(config.gpio_dir[6])
-                    .gpd7(config.gpio_dir[7])
-                    .gpd8(config.gpio_dir[8])
-            })
-            .await?;
-
-        self.ll
-            .gpio_out()
-            .modify(|_, w| {
-                w.gop0(config.output[0])
-                    .gop1(config.output[1])
-                    .gop2(config.output[2])
-                    .gop3(config.output[3])
-                    .gop4(config.output[4])
-                    .gop5(config.output[5])
-                    .gop6(config.output[6])
-                    .gop7(config.output[7])
-                    .gop8(config.output[8])
-            })
-            .await?;
-
-        Ok(())
-    }
-
-    /// Enable gpios clocks
-    #[maybe_async_attr]
-    pub async fn gpio_config_clocks(&mut self) -> Result<(), Error<SPI>> {
-        self.ll
-            .clk_ctrl()
-            .modify(|_, w| {
-                w.gpio_clk_en(0b1)
-                    .gpio_dclk_en(0b1)
-                    .gpio_drst_n(0b1)
-                    .lp_clk_en(0b1)
-            })
-            .await?;
-
-        self.ll
-            .led_ctrl()
-            .modify(|_, w| w.blink_en(0b1).blink_tim(0x10).force_trig(0x0))
-            .await?;
-
-        Ok(())
-    }
-
-    /// Enables single pin
-    #[maybe_async_attr]
-    pub async fn gpio_config_enable(&mut self, pin: u8, enable: u8) -> Result<(), Error<SPI>> {
-        self.ll
-            .gpio_pull_en()
-            .modify(|_, w| match pin {
-                0 => w.mgpen0(enable),
-                1 => w.mgpen1(enable),
-                2 => w.mgpen2(enable),
-                3 => w.mgpen3(enable),
-                4 => w.mgpen4(enable),
-                5 => w.mgpen5(enable),
-                6 => w.mgpen6(enable),
-                7 => w.mgpen7(enable),
-                8 => w.mgpen8(enable),
-                _ => w,
-            })
-            .await?;
-        Ok(())
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_set_extended_address() {
+        let spi = SpiMock::new(&[
+            // The EUI is replaced
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![
+                0xC0, 0x10, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
+            ]),
+            SpiTransaction::transaction_end(),
+            // The short address is unassigned
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0x30, 0xFE, 0xFF, 0x05, 0x06]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
 
-    /// Configures mode for a single pin
-    #[maybe_async_attr]
-    pub async fn gpio_config_mode(&mut self, pin: u8, mode: u8) -> Result<(), Error<SPI>> {
-        self.ll
-            .gpio_mode()
-            .modify(|_, w| match pin {
-                0 => w.msgp0(mode),
-                1 => w.msgp1(mode),
-                2 => w.msgp2(mode),
-                3 => w.msgp3(mode),
-                4 => w.msgp4(mode),
-                5 => w.msgp5(mode),
-                6 => w.msgp6(mode),
-                7 => w.msgp7(mode),
-                8 => w.msgp8(mode),
-                _ => w,
-            })
-            .await?;
-        Ok(())
+        dw3000
+            .set_address(
+                Ieee802154Pan(0x0605),
+                Ieee802154Address::Extended([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]),
+            )
+            .await
+            .unwrap();
+        let result = dw3000
+            .set_address(Ieee802154Pan(0x0605), Ieee802154Address::Absent)
+            .await;
+        assert!(matches!(result, Err(Error::InvalidConfiguration)));
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
     }
 
-    /// Configures direction for a single pin
-    #[maybe_async_attr]
-    pub async fn gpio_config_dir(&mut self, pin: u8, dir: u8) -> Result<(), Error<SPI>> {
-        self.ll
-            .gpio_dir()
-            .modify(|_, w| match pin {
-                0 => w.gpd0(dir),
-                1 => w.gpd1(dir),
-                2 => w.gpd2(dir),
-                3 => w.gpd3(dir),
-                4 => w.gpd4(dir),
-                5 => w.gpd5(dir),
-                6 => w.gpd6(dir),
-                7 => w.gpd7(dir),
-                8 => w.gpd8(dir),
-                _ => w,
-            })
-            .await?;
-        Ok(())
-    }
-
-    /// Configures output for a single pin
-    #[maybe_async_attr]
-    pub async fn gpio_config_out(&mut self, pin: u8, output: u8) -> Result<(), Error<SPI>> {
-        self.ll
-            .gpio_out()
-            .modify(|_, w| match pin {
-                0 => w.gop0(output),
-                1 => w.gop1(output),
-                2 => w.gop2(output),
-                3 => w.gop3(output),
-                4 => w.gop4(output),
-                5 => w.gop5(output),
-                6 => w.gop6(output),
-                7 => w.gop7(output),
-                8 => w.gop8(output),
-                _ => w,
-            })
-            .await?;
-        Ok(())
-    }
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_send_on_sync() {
+        let spi = SpiMock::new(&[
+            // Timebase reset on the next SYNC pulse
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC8, 0x02, 0x0F, 0xF9, 0x08, 0x09]),
+            SpiTransaction::transaction_end(),
+            // Delayed transmission at time 0
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xB0, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x87]),
+            SpiTransaction::transaction_end(),
+            // HPDWARN cleared
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0, 0, 0, 0x08, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
 
-    /// Returns GPIO config
-    #[maybe_async_attr]
-    pub async fn get_gpio_config(&mut self) -> Result<ConfigGPIOs, Error<SPI>> {
-        let enabled = self.get_gpio_enabled().await?;
-        let mode = self.get_gpio_mode().await?;
-        let gpio_dir = self.get_gpio_dir().await?;
-        let output = self.get_gpio_out().await?;
-
-        Ok(ConfigGPIOs {
-            enabled,
-            mode,
-            gpio_dir,
-            output,
-        })
-    }
+        dw3000.start_transmission(SendTime::OnSync).await.unwrap();
 
-    /// Returns current gpio enable state
-    #[maybe_async_attr]
-    pub async fn get_gpio_enabled(&mut self) -> Result<[u8; 9], Error<SPI>> {
-        let gpio_pull_en = self.ll.gpio_pull_en().read().await?;
-        let enabled: [u8; 9] = [
-            gpio_pull_en.mgpen0(),
-            gpio_pull_en.mgpen1(),
-            gpio_pull_en.mgpen2(),
-            gpio_pull_en.mgpen3(),
-            gpio_pull_en.mgpen4(),
-            gpio_pull_en.mgpen5(),
-            gpio_pull_en.mgpen6(),
-            gpio_pull_en.mgpen7(),
-            gpio_pull_en.mgpen8(),
-        ];
-
-        Ok(enabled)
-    }
+        let mut spi = dw3000.ll.spi;
 
-    /// Returns current gpio pin mode
-    #[maybe_async_attr]
-    pub async fn get_gpio_mode(&mut self) -> Result<[u8; 9], Error<SPI>> {
-        let gpio_mode = self.ll.gpio_mode().read().await?;
-        let mode: [u8; 9] = [
-            gpio_mode.msgp0(),
-            gpio_mode.msgp1(),
-            gpio_mode.msgp2(),
-            gpio_mode.msgp3(),
-            gpio_mode.msgp4(),
-            gpio_mode.msgp5(),
-            gpio_mode.msgp6(),
-            gpio_mode.msgp7(),
-            gpio_mode.msgp8(),
-        ];
-
-        Ok(mode)
+        spi.done();
     }
 
-    /// Returns current gpio dir
-    #[maybe_async_attr]
-    pub async fn get_gpio_dir(&mut self) -> Result<[u8; 9], Error<SPI>> {
-        let gpio_direction = self.ll.gpio_dir().read().await?;
-        let gpio_dir = [
-            gpio_direction.gpd0(),
-            gpio_direction.gpd1(),
-            gpio_direction.gpd2(),
-            gpio_direction.gpd3(),
-            gpio_direction.gpd4(),
-            gpio_direction.gpd5(),
-            gpio_direction.gpd6(),
-            gpio_direction.gpd7(),
-            gpio_direction.gpd8(),
-        ];
-
-        Ok(gpio_dir)
-    }
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_prepare_event_counters() {
+        let spi = SpiMock::new(&[
+            // Counters are kept, only enabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x00, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Counters are cleared, then enabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x5E, 0x00, 0], vec![0, 0, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x00, 0x01]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
 
-    /// Returns current output
-    #[maybe_async_attr]
-    pub async fn get_gpio_out(&mut self) -> Result<[u8; 9], Error<SPI>> {
-        let gpio_out = self.ll.gpio_out().read().await?;
-        let output = [
-            gpio_out.gop0(),
-            gpio_out.gop1(),
-            gpio_out.gop2(),
-            gpio_out.gop3(),
-            gpio_out.gop4(),
-            gpio_out.gop5(),
-            gpio_out.gop6(),
-            gpio_out.gop7(),
-            gpio_out.gop8(),
-        ];
-
-        Ok(output)
-    }
+        let config = Config {
+            clear_event_counters: false,
+            ..Config::default()
+        };
+        dw3000.prepare_event_counters(&config).await.unwrap();
+        dw3000
+            .prepare_event_counters(&Config::default())
+            .await
+            .unwrap();
 
-    /// Returns current raw state / input
-    #[maybe_async_attr]
-    pub async fn get_gpio_raw_state(&mut self) -> Result<[u8; 9], Error<SPI>> {
-        let gpio_raw = self.ll.gpio_raw().read().await?;
-        let raw = [
-            gpio_raw.grawp0(),
-            gpio_raw.grawp1(),
-            gpio_raw.grawp2(),
-            gpio_raw.grawp3(),
-            gpio_raw.grawp4(),
-            gpio_raw.grawp5(),
-            gpio_raw.grawp6(),
-            gpio_raw.grawp7(),
-            gpio_raw.grawp8(),
-        ];
-
-        Ok(raw)
-    }
-}
+        let mut spi = dw3000.ll.spi;
 
-/// General configuration for GPIO
-#[derive(Debug)]
-pub struct ConfigGPIOs {
-    /// Enables (1) or disables (0) pins
-    pub enabled: [u8; 9],
-    /// Pin mode
-    pub mode: [u8; 9],
-    /// Set GPIO pins as input (1) or output (0)
-    pub gpio_dir: [u8; 9],
-    /// Set GPIO high (1) or low (0)
-    pub output: [u8; 9],
-}
-impl Default for ConfigGPIOs {
-    fn default() -> Self {
-        ConfigGPIOs {
-            enabled: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            mode: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-            gpio_dir: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            output: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-        }
-    }
-}
-impl ConfigGPIOs {
-    /// Disables all 4 leds
-    pub fn disable_led() -> Self {
-        ConfigGPIOs {
-            enabled: [1, 1, 0, 0, 1, 1, 1, 1, 1],
-            mode: [0, 0, 1, 1, 0, 0, 0, 0, 0],
-            gpio_dir: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            output: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-        }
-    }
-    /// Enables only RX and TX led
-    pub fn enable_led() -> Self {
-        ConfigGPIOs {
-            enabled: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            mode: [0, 0, 1, 1, 0, 0, 0, 0, 0],
-            gpio_dir: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            output: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-        }
-    }
-    /// Set everything to 0
-    pub fn all_0() -> Self {
-        ConfigGPIOs {
-            enabled: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-            mode: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-            gpio_dir: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-            output: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-        }
-    }
-    /// Set everything to 1
-    pub fn all_1() -> Self {
-        ConfigGPIOs {
-            enabled: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            mode: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            gpio_dir: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-            output: [1, 1, 1, 1, 1, 1, 1, 1, 1],
-        }
-    }
-    /// Custom config for debug
-    pub fn custom() -> Self {
-        ConfigGPIOs {
-            enabled: [1, 1, 1, 1, 0, 0, 0, 0, 0],
-            mode: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-            gpio_dir: [1, 1, 0, 0, 0, 0, 0, 0, 0],
-            output: [0, 0, 0, 0, 0, 0, 0, 0, 0],
-        }
+        spi.done();
     }
 }
@@ -10,11 +10,13 @@ use num_traits::Float;
 #[cfg(feature = "defmt")]
 use defmt::Format;
 
-use super::{AutoDoubleBufferReceiving, ReceiveTime, Receiving};
+use super::{
+    AutoDoubleBufferReceiving, ClockOffsetSource, ExternalSyncTime, Pdoa, ReceiveTime, Receiving,
+};
 use crate::{
-    configs::{BitRate, PulseRepetitionFrequency, SfdSequence},
+    configs::{BitRate, PulseRepetitionFrequency, SfdSequence, StsMode},
     maybe_async_attr, spi_type,
-    time::Instant,
+    time::{Instant, SignedDuration},
     Config, Error, FastCommand, Ready, DW3000,
 };
 
@@ -33,6 +35,31 @@ pub struct Message<'l> {
     /// quality of the message received
     pub rx_quality: RxQuality,
 
+    /// The phase difference of arrival
+    ///
+    /// Only available if PDoA is enabled (see [`DW3000::set_pdoa_mode`]).
+    pub pdoa: Option<Pdoa>,
+
+    /// The time difference of arrival between the two CIRs
+    ///
+    /// This is the difference between the first path arrival times in the
+    /// Ipatov and STS CIRs, or the STS0 and STS1 CIRs in PDoA mode 3. Only
+    /// available if STS is enabled (see [`Config::sts_mode`]).
+    pub tdoa: Option<SignedDuration>,
+
+    /// The clock offset between the local and the sender's crystal in ppm
+    ///
+    /// Computed from the carrier recovery integrator, see
+    /// [`DW3000::clock_offset_ppm`]. Only available if
+    /// [`Config::read_clock_offset`] is set.
+    pub clock_offset_ppm: Option<f32>,
+
+    /// The RX timestamp relative to the last SYNC pulse
+    ///
+    /// Only available if [`Config::read_sync_time`] is set and the SYNC pin
+    /// resets the timebase (see [`DW3000::set_sync_behaviour`]).
+    pub sync_time: Option<ExternalSyncTime>,
+
     /// The MAC frame
     pub frame: Ieee802154Frame<&'l [u8]>,
 }
@@ -78,8 +105,8 @@ where
         if config.frame_filtering {
             self.ll
                 .sys_cfg()
-                .modify(
-                    |_, w| w.ffen(0b1), // enable frame filtering
+                .modify_masked(
+                    |w| w.ffen(0b1), // enable frame filtering
                 )
                 .await?;
             self.ll
@@ -94,7 +121,7 @@ where
                 )
                 .await?;
         } else {
-            self.ll.sys_cfg().modify(|_, w| w.ffen(0b0)).await?; // disable frame filtering
+            self.ll.sys_cfg().modify_masked(|w| w.ffen(0b0)).await?; // disable frame filtering
         }
 
         match recv_time {
@@ -210,6 +237,25 @@ where
             rssi,
         };
 
+        // Only read the diagnostics that are enabled, to keep the number of
+        // SPI transactions per frame low
+        let config = *self.state.get_rx_config();
+        let pdoa = self.read_pdoa().await?;
+        let tdoa = self.read_tdoa().await?;
+        let clock_offset_ppm = if config.read_clock_offset {
+            Some(
+                self.clock_offset_ppm(ClockOffsetSource::CarrierIntegrator)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        let sync_time = if config.read_sync_time {
+            self.read_external_sync_time().await?
+        } else {
+            None
+        };
+
         // Reset status bits. This is not strictly necessary, but it helps, if
         // you have to inspect SYS_STATUS manually during debugging.
         // NOTE: The `SYS_STATUS` register is write-to-clear
@@ -230,6 +276,7 @@ where
                     .rxovrr(0b1) // Receiver Overrun
                     .rxpto(0b1) // Preamble Detection Timeout
                     .rxsto(0b1) // Receiver SFD Timeout
+                    .arfe(0b1) // Automatic Frame Filtering Rejection
                     .rxprej(0b1) // Receiver Preamble Rejection
             })
             .await
@@ -272,6 +319,10 @@ where
         Ok(Message {
             rx_time,
             rx_quality,
+            pdoa,
+            tdoa,
+            clock_offset_ppm,
+            sync_time,
             frame,
         })
     }
@@ -383,6 +434,7 @@ where
                     .rxovrr(0b1) // Receiver Overrun
                     .rxpto(0b1) // Preamble Detection Timeout
                     .rxsto(0b1) // Receiver SFD Timeout
+                    .arfe(0b1) // Automatic Frame Filtering Rejection
                     .rxprej(0b1) // Receiver Preamble Rejection
             })
             .await
@@ -417,6 +469,35 @@ where
         Ok((len, rx_time, rx_quality))
     }
 
+    /// Reads the phase difference of arrival of the last received frame
+    ///
+    /// Returns `None` if PDoA is disabled.
+    #[maybe_async_attr]
+    pub async fn read_pdoa(&mut self) -> Result<Option<Pdoa>, Error<SPI>> {
+        if self.ll.sys_cfg().read().await?.pdoa_mode() == 0 {
+            return Ok(None);
+        }
+
+        let pdoa = self.ll.pdoa().read().await?;
+
+        Ok(Some(Pdoa::from_register(pdoa.pdoa(), pdoa.fp_th_md())))
+    }
+
+    /// Reads the time difference of arrival between the two CIRs of the last
+    /// received frame
+    ///
+    /// Returns `None` if STS is disabled, as there is only one CIR then.
+    #[maybe_async_attr]
+    pub async fn read_tdoa(&mut self) -> Result<Option<SignedDuration>, Error<SPI>> {
+        if self.state.get_rx_config().sts_mode == StsMode::StsModeOff {
+            return Ok(None);
+        }
+
+        let tdoa = self.ll.tdoa().read().await?.value();
+
+        Ok(Some(SignedDuration::from_tdoa(tdoa)))
+    }
+
     /// DW3000 User Manual 4.7.1
     /// returns dBm
     #[cfg(feature = "rssi")]
@@ -489,8 +570,9 @@ where
     #[allow(clippy::type_complexity)]
     /// Finishes receiving and returns to the `Ready` state
     ///
-    /// If the receive operation has finished, as indicated by `wait`, this is a
-    /// no-op. If the receive operation is still ongoing, it will be aborted.
+    /// If the receive operation is still ongoing, as indicated by `wait`, it
+    /// will be aborted. SNIFF mode is disabled if the receiver was started
+    /// with `receive_sniff`.
     #[maybe_async_attr]
     pub async fn finish_receiving(mut self) -> Result<DW3000<SPI, Ready>, (Self, Error<SPI>)> {
         // TO DO : if we are not in state 3 (IDLE), we need to have a reset of the module (with a new initialisation)
@@ -503,6 +585,12 @@ where
             }
         }
 
+        if self.state.is_sniffing() {
+            if let Err(error) = self.ll.rx_sniff().write(|w| w).await {
+                return Err((self, Error::Spi(error)));
+            }
+        }
+
         Ok(DW3000 {
             ll: self.ll,
             seq: self.seq,
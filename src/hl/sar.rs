@@ -0,0 +1,172 @@
+//! Temperature and battery voltage measurement with the on-chip SAR ADC
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+
+use super::{Awake, OtpData};
+use crate::{maybe_async_attr, spi_type, Error, Ready, DW3000};
+
+/// LDOs that have to be enabled for the SAR to measure the temperature
+const LDO_CTRL_SAR_ENABLE: u16 = 0x0007;
+
+/// Interval at which `SAR_STATUS` is polled, in µs
+const SAR_POLL_INTERVAL_US: u32 = 10;
+
+/// Time after which a SAR measurement is abandoned, in µs
+///
+/// A measurement takes a few µs, so this only triggers if the SAR is stuck.
+const SAR_TIMEOUT_US: u32 = 1000;
+
+/// A temperature in degrees Celsius
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct Celsius(pub f32);
+
+/// A voltage in Volts
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct Volts(pub f32);
+
+/// Converts a raw SAR temperature reading
+///
+/// `calibration` is the reading taken at 22 °C, stored in OTP.
+fn convert_temperature(raw: u8, calibration: u8) -> Celsius {
+    Celsius((raw as f32 - calibration as f32) * 1.05 + 22.0)
+}
+
+/// Converts a raw SAR voltage reading
+///
+/// `calibration` is the reading taken at 3.0 V, stored in OTP.
+fn convert_voltage(raw: u8, calibration: u8) -> Volts {
+    Volts((raw as f32 - calibration as f32) / 57.0 + 3.0)
+}
+
+impl<SPI, State> DW3000<SPI, State>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    State: Awake,
+{
+    /// Returns the temperature and battery voltage sampled at the last wake-up
+    ///
+    /// The DW3000 only samples them on wake-up if it was configured to do so
+    /// before going to sleep.
+    #[maybe_async_attr]
+    pub async fn read_wake_up_temperature_and_voltage(
+        &mut self,
+    ) -> Result<(Celsius, Volts), Error<SPI>> {
+        let reading = self.ll.sar_wake_rd().read().await?;
+
+        self.convert_sar_reading(reading.sar_wtemp(), reading.sar_wvbat())
+            .await
+    }
+
+    #[maybe_async_attr]
+    async fn convert_sar_reading(
+        &mut self,
+        temperature: u8,
+        voltage: u8,
+    ) -> Result<(Celsius, Volts), Error<SPI>> {
+        let temperature_calibration = (self.read_otp(OtpData::VTEMP_ADDRESS).await? & 0xFF) as u8;
+        let voltage_calibration = (self.read_otp(OtpData::VBAT_ADDRESS).await? & 0xFF) as u8;
+
+        // Parts without calibration data can't be converted reliably
+        if temperature_calibration == 0 || voltage_calibration == 0 {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        Ok((
+            convert_temperature(temperature, temperature_calibration),
+            convert_voltage(voltage, voltage_calibration),
+        ))
+    }
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Measures the temperature of the DW3000 and its supply voltage
+    ///
+    /// The readings are converted with the calibration values stored in OTP.
+    /// Returns [`Error::InvalidConfiguration`] if the OTP contains none, and
+    /// [`Error::Timeout`] if the measurement doesn't finish within 1 ms.
+    /// `delay` is used to wait between polls of the SAR.
+    #[maybe_async_attr]
+    pub async fn read_temperature_and_voltage<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<(Celsius, Volts), Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let (temperature, voltage) = self.read_sar(delay).await?;
+
+        self.convert_sar_reading(temperature, voltage).await
+    }
+
+    /// Runs a SAR measurement and returns the raw temperature and voltage
+    #[maybe_async_attr]
+    pub(crate) async fn read_sar<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<(u8, u8), Error<SPI>>
+    where
+        DELAY: DelayNs,
+    {
+        let ldo_ctrl = self.ll.ldo_ctrl().read().await?.low();
+
+        // Enable the LDOs and the temperature sensor, then start the SAR
+        self.ll
+            .ldo_ctrl()
+            .modify(|_, w| w.low(ldo_ctrl | LDO_CTRL_SAR_ENABLE))
+            .await?;
+        self.ll.rsar_test().modify(|_, w| w.sar_rden(1)).await?;
+        self.ll.sar_ctrl().write(|w| w.sar_start(1)).await?;
+
+        let mut done = false;
+        let mut remaining = SAR_TIMEOUT_US;
+        loop {
+            if self.ll.sar_status().read().await?.sar_done() == 1 {
+                done = true;
+                break;
+            }
+            if remaining == 0 {
+                break;
+            }
+            delay.delay_us(SAR_POLL_INTERVAL_US).await;
+            remaining = remaining.saturating_sub(SAR_POLL_INTERVAL_US);
+        }
+
+        let reading = self.ll.sar_reading().read().await?;
+
+        // Stop the SAR and restore the previous configuration
+        self.ll.sar_ctrl().write(|w| w.sar_start(0)).await?;
+        self.ll.rsar_test().modify(|_, w| w.sar_rden(0)).await?;
+        self.ll.ldo_ctrl().modify(|_, w| w.low(ldo_ctrl)).await?;
+
+        if !done {
+            return Err(Error::Timeout);
+        }
+
+        Ok((reading.sar_ltemp(), reading.sar_lvbat()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sar_conversion() {
+        assert_eq!(convert_temperature(0x85, 0x85), Celsius(22.0));
+        assert!((convert_temperature(0x8F, 0x85).0 - 32.5).abs() < 1e-3);
+
+        assert_eq!(convert_voltage(0x74, 0x74), Volts(3.0));
+        assert!((convert_voltage(0x74 + 19, 0x74).0 - 3.333).abs() < 1e-3);
+    }
+}
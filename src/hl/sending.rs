@@ -31,21 +31,6 @@ where
     #[inline(always)]
     #[maybe_async_attr]
     pub async fn s_wait(&mut self) -> nb::Result<Instant, Error<SPI>> {
-        // Check Half Period Warning Counter. If this is a delayed transmission,
-        // this will indicate that the delay was too short, and the frame was
-        // sent too late.
-        let evc_hpw = self
-            .ll
-            .evc_hpw()
-            .read()
-            .await
-            .map_err(|error| nb::Error::Other(Error::Spi(error)))?
-            .value();
-
-        if evc_hpw != 0 {
-            return Err(nb::Error::Other(Error::DelayedSendTooLate));
-        }
-
         // WARNING s:
         // If you're changing anything about which SYS_STATUS flags are being
         // checked in this method, also make sure to update `enable_interrupts`.
@@ -56,6 +41,13 @@ where
             .await
             .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
 
+        // Check the Half Period Delay Warning. If this is a delayed
+        // transmission, this indicates that the delay was too short, and the
+        // frame would be sent too late.
+        if sys_status.hpdwarn() == 0b1 {
+            return Err(nb::Error::Other(Error::DelayedSendTooLate));
+        }
+
         // Has the frame been sent?
         if sys_status.txfrs() == 0b0 {
             // Frame has not been sent
@@ -121,9 +113,57 @@ where
                     .txprs(0b1) // Transmit Preamble Sent
                     .txphs(0b1) // Transmit PHY Header Sent
                     .txfrs(0b1) // Transmit Frame Sent
+                    .hpdwarn(0b1) // Half Period Delay Warning
             })
             .await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_s_wait() {
+        let spi = SpiMock::new(&[
+            // SYS_STATUS with TXFRS set
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x10, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0xF0, 0, 0, 0, 0, 0],
+            ),
+            SpiTransaction::transaction_end(),
+            // TX events cleared
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x10, 0xF0, 0, 0, 0x08, 0, 0]),
+            SpiTransaction::transaction_end(),
+            // TX_TIME
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0xD0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x10, 0x00, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Sending { finished: false },
+        };
+
+        let tx_time = dw3000.s_wait().await.unwrap();
+        assert_eq!(tx_time.value(), 0x1000);
+        let dw3000 = dw3000.finish_sending().await.unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -0,0 +1,183 @@
+//! SNIFF mode, a low duty cycle preamble hunt
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use crate::{
+    configs::PulseRepetitionFrequency, maybe_async_attr, spi_type, Config, Error, Ready,
+    SingleBufferReceiving, DW3000,
+};
+
+use super::ReceiveTime;
+
+/// Duration of a SNIFF off time unit in µs (512 / 499.2 MHz)
+const OFF_TIME_UNIT_US: f32 = 512.0 / 499.2;
+
+/// Configuration of the SNIFF mode
+///
+/// In SNIFF mode, the receiver alternates between hunting for a preamble for
+/// `on_pacs` preamble acquisition chunks (PACs) and sleeping for `off_time`.
+/// Frames are only caught if their preamble is longer than the off time plus
+/// the on time, so this is meant for long preamble wake-up frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct SniffConfig {
+    /// The time the receiver is on, in PACs (1 to 15)
+    ///
+    /// The hardware adds one PAC, so the receiver is on for `on_pacs + 1`
+    /// PACs.
+    pub on_pacs: u8,
+    /// The time the receiver is off, in µs (1 to 261)
+    pub off_time: u16,
+}
+
+impl SniffConfig {
+    /// Returns the `SNIFF_OFF` register value, in units of 512 / 499.2 µs
+    ///
+    /// Returns `None` if the off time is out of range.
+    fn off_time_register(&self) -> Option<u8> {
+        let value = self.off_time as f32 / OFF_TIME_UNIT_US + 0.5;
+
+        match value as u32 {
+            0 => None,
+            value => u8::try_from(value).ok(),
+        }
+    }
+
+    /// Returns the off time after rounding to the register's resolution in µs
+    pub fn effective_off_time(&self) -> Option<f32> {
+        Some(self.off_time_register()? as f32 * OFF_TIME_UNIT_US)
+    }
+
+    /// Returns the on time in µs for the given radio configuration
+    pub fn on_time(&self, config: &Config) -> f32 {
+        let pac_symbols = match config.preamble_length.get_recommended_pac_size() {
+            0 => 8,
+            1 => 16,
+            2 => 32,
+            _ => 4,
+        };
+        let symbol_time = match config.pulse_repetition_frequency {
+            PulseRepetitionFrequency::Mhz16 => 0.99359,
+            PulseRepetitionFrequency::Mhz64 => 1.01763,
+        };
+
+        (self.on_pacs + 1) as f32 * pac_symbols as f32 * symbol_time
+    }
+
+    /// Returns the fraction of time the receiver is listening
+    ///
+    /// Returns `None` if the configuration is invalid.
+    pub fn duty_cycle(&self, config: &Config) -> Option<f32> {
+        if !(1..=15).contains(&self.on_pacs) {
+            return None;
+        }
+
+        let on = self.on_time(config);
+        Some(on / (on + self.effective_off_time()?))
+    }
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Attempt to receive a single IEEE 802.15.4 MAC frame in SNIFF mode
+    ///
+    /// Works like [`DW3000::receive`], but the receiver is duty cycled while
+    /// it is hunting for a preamble. SNIFF mode is disabled again by
+    /// [`DW3000::finish_receiving`].
+    ///
+    /// Returns [`Error::InvalidConfiguration`] if `sniff` is out of range.
+    #[maybe_async_attr]
+    pub async fn receive_sniff(
+        mut self,
+        config: Config,
+        sniff: SniffConfig,
+    ) -> Result<DW3000<SPI, SingleBufferReceiving>, Error<SPI>> {
+        if !(1..=15).contains(&sniff.on_pacs) {
+            return Err(Error::InvalidConfiguration);
+        }
+        let off_time = sniff
+            .off_time_register()
+            .ok_or(Error::InvalidConfiguration)?;
+
+        self.ll
+            .rx_sniff()
+            .write(|w| w.sniff_on(sniff.on_pacs).sniff_off(off_time))
+            .await?;
+
+        let mut receiving = self.receive_delayed(ReceiveTime::Now, config).await?;
+        receiving.state.sniff = true;
+
+        Ok(receiving)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[test]
+    fn sniff_timing() {
+        let sniff = SniffConfig {
+            on_pacs: 2,
+            off_time: 100,
+        };
+        assert_eq!(sniff.off_time_register(), Some(98));
+        assert!((sniff.effective_off_time().unwrap() - 100.513).abs() < 1e-3);
+
+        // 64 MHz PRF with 128 symbols preamble uses a PAC of 16 symbols, the
+        // hardware adds one PAC to the on time
+        let config = Config::default();
+        assert!((sniff.on_time(&config) - 48.846).abs() < 1e-3);
+        assert!((sniff.duty_cycle(&config).unwrap() - 0.3270).abs() < 1e-3);
+
+        assert_eq!(
+            SniffConfig {
+                on_pacs: 2,
+                off_time: 300
+            }
+            .duty_cycle(&config),
+            None
+        );
+        assert_eq!(
+            SniffConfig {
+                on_pacs: 0,
+                off_time: 100
+            }
+            .duty_cycle(&config),
+            None
+        );
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_finish_sniff_receiving() {
+        let spi = SpiMock::new(&[
+            // SNIFF mode is disabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x68, 0, 0, 0, 0]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: SingleBufferReceiving {
+                finished: true,
+                config: Config::default(),
+                sniff: true,
+            },
+        };
+
+        let dw3000 = dw3000.finish_receiving().await.unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -1,3 +1,4 @@
+use super::SavedTxBlocks;
 use crate::Config;
 
 /// Indicates that the `DW3000` instance is not initialized yet
@@ -31,6 +32,8 @@ impl Sending {
 pub struct SingleBufferReceiving {
     pub(super) finished: bool,
     pub(super) config: Config,
+    /// The receiver was started by `receive_sniff`
+    pub(super) sniff: bool,
 }
 
 /// Indicates that the `DW3000` instance is currently receiving in double buffer
@@ -41,6 +44,19 @@ pub struct AutoDoubleBufferReceiving {
     pub(super) config: Config,
 }
 
+/// Indicates that the `DW3000` instance is transmitting a test signal
+#[derive(Debug)]
+pub struct TestMode {
+    /// The transmitter configuration before the test started
+    pub(super) saved: SavedTxBlocks,
+    /// `RF_CTRL_MASK` before the test started
+    pub(super) rf_ctrl_mask: u32,
+    /// `RF_SWITCH` before the test started
+    pub(super) rf_switch: u32,
+    /// `TXFSEQ` before the test started
+    pub(super) txfseq: u32,
+}
+
 /// Indicates that the `DW3000` instance is currently sleeping
 #[derive(Debug)]
 pub struct Sleeping {
@@ -56,6 +72,7 @@ impl Awake for Ready {}
 impl Awake for Sending {}
 impl Awake for SingleBufferReceiving {}
 impl Awake for AutoDoubleBufferReceiving {}
+impl Awake for TestMode {}
 /// Any state struct that implements this trait signals that the radio is
 /// sleeping.
 pub trait Asleep {}
@@ -76,6 +93,10 @@ pub trait Receiving: Awake {
     fn is_finished(&self) -> bool;
     /// Get the rx radio config
     fn get_rx_config(&self) -> &Config;
+    /// Return true if the receiver was started in SNIFF mode
+    fn is_sniffing(&self) -> bool {
+        false
+    }
 }
 
 impl Receiving for SingleBufferReceiving {
@@ -93,6 +114,10 @@ impl Receiving for SingleBufferReceiving {
     fn get_rx_config(&self) -> &Config {
         &self.config
     }
+
+    fn is_sniffing(&self) -> bool {
+        self.sniff
+    }
 }
 
 impl Receiving for AutoDoubleBufferReceiving {
@@ -0,0 +1,191 @@
+//! External synchronisation through the SYNC pin
+//!
+//! The DW3000 synchronises to an external clock with the one-shot timebase
+//! reset: `OSTS_WAIT` cycles of the 38.4 MHz clock after a SYNC pulse, the
+//! system time is reset to 0 (`EC_CTRL.OSTR_MODE`). Nodes that share the SYNC
+//! line therefore share one timebase, and all timestamps count from the last
+//! pulse.
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::{Gpio7Function, GpioFunction, Receiving, SyncBehaviour};
+use crate::{maybe_async_attr, spi_type, time::Duration, Error, Ready, DW3000};
+
+/// Number of 38.4 MHz cycles between the SYNC pulse and the synchronised event
+pub(crate) const SYNC_WAIT: u8 = 33;
+
+/// An RX timestamp relative to the last SYNC pulse
+///
+/// Only available in [`SyncBehaviour::TimeBaseReset`],
+/// [`SyncBehaviour::ExternalSync`] and [`SyncBehaviour::ExternalSyncWithReset`]
+/// modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub struct ExternalSyncTime {
+    /// The time from the timebase reset to the RMARKER of the frame
+    pub since_sync: Duration,
+}
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Configures what happens on a pulse of the SYNC pin
+    ///
+    /// Puts GPIO7 into its SYNC function and enables the one-shot timebase
+    /// reset, unless `behaviour` is [`SyncBehaviour::None`], in which case
+    /// the SYNC pin is ignored.
+    #[maybe_async_attr]
+    pub async fn set_sync_behaviour(&mut self, behaviour: SyncBehaviour) -> Result<(), Error<SPI>> {
+        let enabled = behaviour != SyncBehaviour::None;
+
+        if enabled {
+            self.ll
+                .gpio_mode()
+                .modify(|_, w| w.msgp7(Gpio7Function::Sync.mode()))
+                .await?;
+        }
+
+        self.ll
+            .seq_ctrl()
+            .modify(|_, w| w.pll_sync(enabled as u8))
+            .await?;
+        self.ll
+            .ec_ctrl()
+            .modify_masked(|w| w.ostr_mode(enabled as u8).osts_wait(SYNC_WAIT))
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl<SPI, RECEIVING> DW3000<SPI, RECEIVING>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+    RECEIVING: Receiving,
+{
+    /// Reads the RX timestamp of the last received frame relative to the last
+    /// SYNC pulse
+    ///
+    /// As the SYNC pulse resets the system time, this is the RX timestamp in
+    /// the synchronised timebase. Returns `None` if the timebase reset is
+    /// disabled.
+    #[maybe_async_attr]
+    pub async fn read_external_sync_time(
+        &mut self,
+    ) -> Result<Option<ExternalSyncTime>, Error<SPI>> {
+        if self.ll.ec_ctrl().read().await?.ostr_mode() == 0 {
+            return Ok(None);
+        }
+
+        let rx_time = self.ll.rx_time().read().await?.rx_stamp();
+
+        Ok(Duration::new(rx_time).map(|since_sync| ExternalSyncTime { since_sync }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ll, Config, SingleBufferReceiving};
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_set_sync_behaviour() {
+        let spi = SpiMock::new(&[
+            // GPIO7 from GPIO to SYNC
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4A, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x00, 0x20, 0x01],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCA, 0x00, 0x00, 0x00, 0x00, 0x01]),
+            SpiTransaction::transaction_end(),
+            // PLL_SYNC
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x62, 0x20, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x01, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x20, 0x00, 0x81, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // OSTR_MODE with OSTS_WAIT = 33
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC8, 0x02, 0x0F, 0xF9, 0x08, 0x09]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        dw3000
+            .set_sync_behaviour(SyncBehaviour::ExternalSyncWithReset)
+            .await
+            .unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_read_external_sync_time() {
+        let spi = SpiMock::new(&[
+            // EC_CTRL with OSTR_MODE set
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x48, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x08, 0x09, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+            // RX_TIME
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x41, 0x90, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x90, 0x78, 0x56, 0x34, 0x12],
+            ),
+            SpiTransaction::transaction_end(),
+            // EC_CTRL without OSTR_MODE
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x48, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x08, 0x01, 0x00, 0x00],
+            ),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: SingleBufferReceiving {
+                finished: false,
+                config: Config::default(),
+                sniff: false,
+            },
+        };
+
+        let sync_time = dw3000.read_external_sync_time().await.unwrap();
+        assert_eq!(
+            sync_time,
+            Some(ExternalSyncTime {
+                since_sync: Duration::new(0x12_3456_7890).unwrap(),
+            })
+        );
+        let sync_time = dw3000.read_external_sync_time().await.unwrap();
+        assert_eq!(sync_time, None);
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -0,0 +1,449 @@
+//! Continuous wave and continuous frame test modes for regulatory testing
+
+use super::TXFSEQ_DISABLED;
+use crate::{
+    configs::UwbChannel, maybe_async_attr, spi_type, time::Duration, Config, Error, FastCommand,
+    Ready, TestMode, DW3000,
+};
+
+/// `TX_TEST` value that enables the transmitter test mode
+const TX_TEST_ENABLE: u8 = 0x0F;
+/// `PG_TEST` value that makes the pulse generator output a continuous wave
+const PG_TEST_CONTINUOUS_WAVE: u16 = 0x0002;
+
+/// Minimum frame period in continuous frame mode, in units of `DX_TIME`
+const MIN_FRAME_PERIOD: u32 = 2;
+
+impl<SPI> DW3000<SPI, Ready>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Starts transmitting an unmodulated carrier
+    ///
+    /// `channel` has to match the channel the DW3000 has been configured
+    /// for, otherwise [`Error::InvalidConfiguration`] is returned. The carrier
+    /// is transmitted until [`DW3000::stop`] is called.
+    #[maybe_async_attr]
+    pub async fn start_continuous_wave(
+        mut self,
+        channel: UwbChannel,
+    ) -> Result<DW3000<SPI, TestMode>, Error<SPI>> {
+        if self.ll.chan_ctrl().read().await?.rf_chan() != channel as u8 {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        let mut test = self.enter_test_mode().await?;
+
+        test.ll
+            .tx_test()
+            .write(|w| w.tx_entest(TX_TEST_ENABLE))
+            .await?;
+        test.ll
+            .pg_test()
+            .write(|w| w.value(PG_TEST_CONTINUOUS_WAVE))
+            .await?;
+
+        Ok(test)
+    }
+
+    /// Starts transmitting `frame` repeatedly
+    ///
+    /// A new frame is started every `period`, which has a resolution of about
+    /// 4 ns and has to be longer than the frame itself. The frames are sent
+    /// with the bitrate and preamble of `config`, until [`DW3000::stop`] is
+    /// called. Like `dwt_configcontinuousframemode`, this disables fine grain
+    /// TX sequencing, which [`DW3000::stop`] restores.
+    ///
+    /// Will panic if `frame` doesn't fit into the TX buffer.
+    #[maybe_async_attr]
+    pub async fn start_continuous_frame(
+        mut self,
+        frame: &[u8],
+        period: Duration,
+        config: &Config,
+    ) -> Result<DW3000<SPI, TestMode>, Error<SPI>> {
+        self.write_tx_frame(frame, config).await?;
+
+        let mut test = self.enter_test_mode().await?;
+
+        test.ll.txfseq().write(|w| w.value(TXFSEQ_DISABLED)).await?;
+        test.ll.diag_tmc().modify_masked(|w| w.tx_pstm(1)).await?;
+        let period = ((period.value() >> 8) as u32).max(MIN_FRAME_PERIOD);
+        test.ll.dx_time().write(|w| w.value(period)).await?;
+        test.fast_cmd(FastCommand::CMD_TX).await?;
+
+        Ok(test)
+    }
+
+    /// Enables the transmitter blocks and the TX path of the RF switch
+    #[maybe_async_attr]
+    async fn enter_test_mode(mut self) -> Result<DW3000<SPI, TestMode>, Error<SPI>> {
+        let rf_ctrl_mask = self.ll.rf_ctrl_mask().read().await?.value();
+        let rf_switch = self.ll.rf_switch().read().await?.value();
+        let txfseq = self.ll.txfseq().read().await?.value();
+        let saved = self.enable_tx_blocks().await?;
+
+        let rf_enable = self.ll.rf_enable().read().await?.value();
+        self.ll
+            .rf_ctrl_mask()
+            .write(|w| w.value(rf_ctrl_mask | rf_enable))
+            .await?;
+        self.ll
+            .rf_switch()
+            .modify(|_, w| w.antswen(1).antswctrl(1).trxswen(1).trxswctrl(1))
+            .await?;
+
+        Ok(DW3000 {
+            ll: self.ll,
+            seq: self.seq,
+            state: TestMode {
+                saved,
+                rf_ctrl_mask,
+                rf_switch,
+                txfseq,
+            },
+        })
+    }
+}
+
+impl<SPI> DW3000<SPI, TestMode>
+where
+    SPI: spi_type::spi::SpiDevice<u8>,
+{
+    /// Stops the test signal and returns to the `Ready` state
+    ///
+    /// Restores the transmitter configuration from before the test.
+    #[allow(clippy::type_complexity)]
+    #[maybe_async_attr]
+    pub async fn stop(mut self) -> Result<DW3000<SPI, Ready>, (Self, Error<SPI>)> {
+        if let Err(error) = self.leave_test_mode().await {
+            return Err((self, error));
+        }
+
+        Ok(DW3000 {
+            ll: self.ll,
+            seq: self.seq,
+            state: Ready,
+        })
+    }
+
+    /// Stops transmitting and disables the test modes
+    #[maybe_async_attr]
+    async fn leave_test_mode(&mut self) -> Result<(), Error<SPI>> {
+        self.force_idle().await?;
+
+        self.ll.diag_tmc().modify_masked(|w| w.tx_pstm(0)).await?;
+        self.ll.tx_test().write(|w| w.tx_entest(0)).await?;
+        self.ll.pg_test().write(|w| w.value(0)).await?;
+
+        let rf_ctrl_mask = self.state.rf_ctrl_mask;
+        let rf_switch = self.state.rf_switch;
+        let txfseq = self.state.txfseq;
+        self.ll.txfseq().write(|w| w.value(txfseq)).await?;
+        self.ll
+            .rf_ctrl_mask()
+            .write(|w| w.value(rf_ctrl_mask))
+            .await?;
+        self.ll.rf_switch().write(|w| w.value(rf_switch)).await?;
+
+        self.disable_tx_blocks(self.state.saved).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll;
+
+    use core::num::Wrapping;
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_continuous_wave() {
+        let spi = SpiMock::new(&[
+            // The DW3000 is configured for channel 5
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x42, 0x50, 0, 0], vec![0; 4]),
+            SpiTransaction::transaction_end(),
+            // Save RF_CTRL_MASK, RF_SWITCH and TXFSEQ
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x50, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x62, 0x48, 0, 0, 0, 0],
+                vec![0, 0, 0x74, 0x88, 0xD2, 0x04],
+            ),
+            SpiTransaction::transaction_end(),
+            // Force the TX clocks and enable the transmitter blocks for channel 5
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x62, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4F, 0x20, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x00, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x11, 0xEE, 0x22]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x42, 0x50, 0, 0], vec![0; 4]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4F, 0x20, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCF, 0x20, 0x00, 0x0B, 0x00, 0x0B]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x00, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x00, 0x00, 0x3C, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+            // RF_CTRL_MASK and the TX path of RF_SWITCH
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4E, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x3C, 0x00, 0x02],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x10, 0x00, 0x3C, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x50, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x50, 0x00, 0x11, 0x01, 0x01]),
+            SpiTransaction::transaction_end(),
+            // TX_TEST and PG_TEST
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0xA0, 0x0F]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD0, 0x60, 0x02, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Stop transmitting and disable the test modes
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x81]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x91, 0xEF, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0xA0, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD0, 0x60, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Restore TXFSEQ, RF_CTRL_MASK and RF_SWITCH
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x74, 0x88, 0xD2, 0x04]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x10, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x50, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Restore the transmitter blocks and clocks
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCF, 0x20, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x11, 0xCC, 0x00]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let test = dw3000
+            .start_continuous_wave(UwbChannel::Channel5)
+            .await
+            .unwrap();
+        let dw3000 = test.stop().await.map_err(|(_, error)| error).unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_continuous_wave_wrong_channel() {
+        let mut spi = SpiMock::new(&[
+            // The DW3000 is configured for channel 5
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x42, 0x50, 0, 0], vec![0; 4]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi.clone()),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let result = dw3000.start_continuous_wave(UwbChannel::Channel9).await;
+        assert!(matches!(result, Err(Error::InvalidConfiguration)));
+
+        spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_continuous_frame() {
+        let mut frame = vec![0xE8, 0x00, 0x01, 0x02, 0x03];
+        frame.resize(2 + 127, 0);
+
+        let spi = SpiMock::new(&[
+            // TX_BUFFER and TX_FCTRL
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(frame),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x40, 0x90, 0, 0, 0, 0, 0, 0], vec![0; 8]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0x90, 0x05, 0x50, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Save RF_CTRL_MASK, RF_SWITCH and TXFSEQ
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x50, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x62, 0x48, 0, 0, 0, 0],
+                vec![0, 0, 0x74, 0x88, 0xD2, 0x04],
+            ),
+            SpiTransaction::transaction_end(),
+            // Force the TX clocks and enable the transmitter blocks for channel 5
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x62, 0x10, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4F, 0x20, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x00, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x11, 0xEE, 0x22]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x42, 0x50, 0, 0], vec![0; 4]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4F, 0x20, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCF, 0x20, 0x00, 0x0B, 0x00, 0x0B]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x00, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x00, 0x00, 0x3C, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+            // RF_CTRL_MASK and the TX path of RF_SWITCH
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x4E, 0x00, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x3C, 0x00, 0x02],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x10, 0x00, 0x3C, 0x00, 0x02]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x4E, 0x50, 0, 0, 0, 0], vec![0; 6]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x50, 0x00, 0x11, 0x01, 0x01]),
+            SpiTransaction::transaction_end(),
+            // Fine grain TX sequencing is disabled
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x74, 0x08, 0xD2, 0x00]),
+            SpiTransaction::transaction_end(),
+            // DIAG_TMC.tx_pstm, DX_TIME and CMD_TX
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x91, 0xFF, 0x10]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xB0, 0x10, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x83]),
+            SpiTransaction::transaction_end(),
+            // Stop transmitting and disable the test modes
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0x81]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xDE, 0x91, 0xEF, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0xA0, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xD0, 0x60, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Restore TXFSEQ, RF_CTRL_MASK and RF_SWITCH
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x48, 0x74, 0x88, 0xD2, 0x04]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x10, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x50, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            // Restore the transmitter blocks and clocks
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCE, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xCF, 0x20, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xE2, 0x11, 0xCC, 0x00]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let dw3000 = DW3000 {
+            ll: ll::DW3000::new(spi),
+            seq: Wrapping(0),
+            state: Ready,
+        };
+
+        let test = dw3000
+            .start_continuous_frame(
+                &[0x01, 0x02, 0x03],
+                Duration::new(0x1000).unwrap(),
+                &Config::default(),
+            )
+            .await
+            .unwrap();
+        let dw3000 = test.stop().await.map_err(|(_, error)| error).unwrap();
+
+        let mut spi = dw3000.ll.spi;
+
+        spi.done();
+    }
+}
@@ -8,6 +8,8 @@ use crate::{
 
 use crate::{maybe_async_attr, spi_type};
 
+use super::{OpsParameterSet, OtpData};
+
 #[cfg(not(feature = "async"))]
 use embedded_hal::delay::DelayNs;
 #[cfg(feature = "async")]
@@ -29,20 +31,6 @@ where
         }
     }
 
-    /// Read the OTP memory at the given address
-    #[maybe_async_attr]
-    pub async fn read_otp(&mut self, addr: u16) -> Result<u32, ll::Error<SPI>> {
-        // Set OTP_MAN to 1
-        self.ll.otp_cfg().write(|w| w.otp_man(1)).await?;
-        // Set the 10-bit address
-        self.ll.otp_addr().modify(|_, w| w.otp_addr(addr)).await?;
-        // Set OTP_READ to 1
-        self.ll.otp_cfg().write(|w| w.otp_read(1)).await?;
-        // Read the data (32 bits)
-        let data = self.ll.otp_rdata().read().await?.value();
-        Ok(data)
-    }
-
     /// Initialize the DW3000
     ///
     /// Basicaly, this is the pll configuration. We want to have a locked pll in order to provide a constant speed clock.
@@ -70,11 +58,11 @@ where
         }
 
         // Read LDO_TUNE value from OTP memory
-        let ldo_tune_l = self.read_otp(0x04).await?;
-        let ldo_tune_h = self.read_otp(0x05).await?;
+        let ldo_tune_l = self.read_otp(OtpData::LDO_TUNE_LOW_ADDRESS).await?;
+        let ldo_tune_h = self.read_otp(OtpData::LDO_TUNE_HIGH_ADDRESS).await?;
 
         // Read BIASTUNE_CAL value from OTP memory (bit 16 to 20)
-        let biastune_cal = self.read_otp(0x0A).await? >> 0x10 & 0x1F;
+        let biastune_cal = self.read_otp(OtpData::BIAS_TUNE_ADDRESS).await? >> 0x10 & 0x1F;
 
         #[cfg(feature = "defmt")]
         defmt::trace!(
@@ -97,10 +85,7 @@ where
         }
 
         // Configuration of `XTAL_TRIM`
-        self.ll.otp_cfg().modify(|_, w| w.otp_man(1)).await?;
-        self.ll.otp_addr().modify(|_, w| w.otp_addr(0x1E)).await?;
-        self.ll.otp_cfg().modify(|_, w| w.otp_read(1)).await?;
-        let xtrim = self.ll.otp_rdata().read().await?.value() & 0x3F;
+        let xtrim = self.read_otp(OtpData::XTAL_TRIM_ADDRESS).await? & 0x3F;
 
         if xtrim != 0 {
             self.ll.xtal().modify(|_, w| w.value(xtrim as u8)).await?;
@@ -108,8 +93,17 @@ where
             self.ll.xtal().modify(|_, w| w.value(0x2E)).await?;
         }
 
+        // Load the factory EUI
+        let eui_low = self.read_otp(OtpData::EUI_LOW_ADDRESS).await?;
+        let eui_high = self.read_otp(OtpData::EUI_HIGH_ADDRESS).await?;
+        let eui = (eui_high as u64) << 32 | eui_low as u64;
+
+        if eui != 0 {
+            self.ll.eui().write(|w| w.value(eui)).await?;
+        }
+
         // Load the PLL code
-        let pll_lock_code = self.read_otp(0x35).await?;
+        let pll_lock_code = self.read_otp(OtpData::PLL_LOCK_CODE_ADDRESS).await?;
 
         if pll_lock_code != 0 {
             self.ll.pll_cc().write(|w| w.value(pll_lock_code)).await?;
@@ -268,7 +262,7 @@ where
         rx_preamble_code: u8,
     ) -> Result<(), Error<SPI>> {
         if (9..=24).contains(&rx_preamble_code) {
-            let dgc_otp = self.read_otp(0x20).await?;
+            let dgc_otp = self.read_otp(OtpData::DGC_ADDRESS).await?;
 
             if dgc_otp == 0x10000240 {
                 #[cfg(feature = "defmt")]
@@ -345,13 +339,13 @@ where
             #[cfg(feature = "defmt")]
             defmt::trace!("PLL is locked, forcing unlock");
 
-            self.ll.clk_ctrl().modify(|_, w| w.sys_clk(0x3)).await?; // Set system to IDLERC
+            self.ll.clk_ctrl().modify_masked(|w| w.sys_clk(0x3)).await?; // Set system to IDLERC
             self.ll.seq_ctrl().modify(|_, w| w.force2init(0x1)).await?; // Force PLL unlock
             self.ll.seq_ctrl().modify(|_, w| w.force2init(0x0)).await?; // Clear force PLL unlock
 
             self.ll
                 .clk_ctrl()
-                .modify(|_, w| {
+                .modify_masked(|w| {
                     w.sys_clk(0)
                         .rx_clk(0)
                         .tx_clk(0)
@@ -396,7 +390,7 @@ where
 
         self.ll
             .clk_ctrl()
-            .modify(|_, w| {
+            .modify_masked(|w| {
                 w.sys_clk(0b00)
                     .rx_clk(0b00)
                     .tx_clk(0b00)
@@ -415,7 +409,7 @@ where
         self.ll.seq_ctrl().modify(|_, w| w.ainit2idle(1)).await?;
 
         // select PLL mode auto
-        self.ll.clk_ctrl().modify(|_, w| w.sys_clk(0)).await?;
+        self.ll.clk_ctrl().modify_masked(|w| w.sys_clk(0)).await?;
         // set ainit2idle
         self.ll.seq_ctrl().modify(|_, w| w.ainit2idle(1)).await?;
         Ok(())
@@ -494,31 +488,28 @@ where
     ) -> Result<(), Error<SPI>> {
         self.ll
             .sys_cfg()
-            .modify(|_, w| w.phr_mode(is_extended_phr as u8))
+            .modify_masked(|w| w.phr_mode(is_extended_phr as u8))
             .await?;
         self.ll
             .sys_cfg()
-            .modify(|_, w| w.phr_6m8(phr_rate as u8))
+            .modify_masked(|w| w.phr_6m8(phr_rate as u8))
             .await?;
         self.ll
             .sys_cfg()
-            .modify(|_, w| w.cp_spc(sts_mode as u8))
+            .modify_masked(|w| w.cp_spc(sts_mode as u8))
             .await?;
         self.ll
             .sys_cfg()
-            .modify(|_, w| w.pdoa_mode(pdoa_mode as u8))
+            .modify_masked(|w| w.pdoa_mode(pdoa_mode as u8))
             .await?;
-        self.ll.sys_cfg().modify(|_, w| w.cp_sdc(0)).await?;
+        self.ll.sys_cfg().modify_masked(|w| w.cp_sdc(0)).await?;
 
         // SCP Mode specific configuration
         if is_scp {
             // TODO: We probably need to adjust our sleep mode accordingly
             //
             // But we don't have a sleep mode yet
-            self.ll
-                .otp_cfg()
-                .modify(|_, w| w.ops_sel(0x1).ops_kick(0x1))
-                .await?;
+            self.load_ops_parameter_set(OpsParameterSet::Scp).await?;
             self.ll
                 .ip_conf_lo()
                 .write(|w| w.ip_ntm(0x6).ip_scp(0x3))
@@ -574,15 +565,9 @@ where
                 // This is DWT_ALT_OPS | DWT_SEL_OPS0 in official driver
                 #[cfg(feature = "defmt")]
                 defmt::trace!("Long preamble detected, setting OTP to DWT_OPSET_LONG");
-                self.ll
-                    .otp_cfg()
-                    .modify(|_, w| w.ops_sel(0x0).ops_kick(1))
-                    .await?; // DWT_OPSET_LONG
+                self.load_ops_parameter_set(OpsParameterSet::Long).await?;
             } else {
-                self.ll
-                    .otp_cfg()
-                    .modify(|_, w| w.ops_sel(0x2).ops_kick(1))
-                    .await?; // DWT_OPSET_SHORT
+                self.load_ops_parameter_set(OpsParameterSet::Short).await?;
             }
         }
         Ok(())
@@ -40,6 +40,6 @@ pub use crate::{
     fast_command::FastCommand,
     hl::{
         AutoDoubleBufferReceiving, Error, Message, Ready, Sending, SingleBufferReceiving, Sleeping,
-        Uninitialized, DW3000,
+        TestMode, Uninitialized, DW3000,
     },
 };
@@ -18,7 +18,9 @@
 use core::fmt::{Display, Formatter};
 use core::{fmt, marker::PhantomData};
 
-use crate::{maybe_async_attr, spi_type};
+use crate::{maybe_async_attr, spi_type, spi_type::spi::Operation};
+
+pub mod trace;
 
 /// Entry point to the DW3000 driver's low-level API
 ///
@@ -29,6 +31,9 @@ use crate::{maybe_async_attr, spi_type};
 pub struct DW3000<SPI> {
     /// SPI device
     pub spi: SPI,
+
+    /// Whether SPI CRC mode is enabled
+    crc: bool,
 }
 
 impl<SPI> DW3000<SPI> {
@@ -36,7 +41,27 @@ impl<SPI> DW3000<SPI> {
     ///
     /// Requires the SPI device
     pub fn new(spi: SPI) -> Self {
-        DW3000 { spi }
+        DW3000 { spi, crc: false }
+    }
+
+    /// Enables or disables SPI CRC mode
+    ///
+    /// In SPI CRC mode, a CRC-8 is appended to every register write, and every
+    /// register read is followed by a read of `SPI_RD_CRC`, which is compared
+    /// against the CRC of the received data. A mismatch is reported as
+    /// [`Error::Crc`].
+    ///
+    /// This only changes the behaviour of the driver. The DW3000 has to be
+    /// switched to SPI CRC mode separately, by setting `SYS_CFG.spi_crcen`.
+    /// Writes with a bad CRC are dropped by the DW3000 and signalled through
+    /// `SYS_STATUS.spicrce` and the `EVC_SWCE` counter.
+    pub fn set_spi_crc(&mut self, enabled: bool) {
+        self.crc = enabled;
+    }
+
+    /// Returns whether SPI CRC mode is enabled
+    pub fn spi_crc(&self) -> bool {
+        self.crc
     }
 
     /// DW3000 fast command
@@ -59,6 +84,99 @@ impl<SPI> DW3000<SPI> {
     pub fn bus(&mut self) -> &mut SPI {
         &mut self.spi
     }
+
+    /// Writes a register write frame, appending the CRC in SPI CRC mode
+    #[maybe_async_attr]
+    async fn write_frame(&mut self, buffer: &[u8]) -> Result<(), Error<SPI>>
+    where
+        SPI: spi_type::spi::SpiDevice<u8>,
+    {
+        if self.crc {
+            let crc = [crc8(0, buffer)];
+            self.spi
+                .transaction(&mut [Operation::Write(buffer), Operation::Write(&crc)])
+                .await
+                .map_err(Error::Transfer)?;
+        } else {
+            SPI::write(&mut self.spi, buffer)
+                .await
+                .map_err(Error::Transfer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compares `expected` against the CRC the DW3000 calculated for the last
+    /// read
+    #[maybe_async_attr]
+    async fn check_read_crc(&mut self, expected: u8) -> Result<(), Error<SPI>>
+    where
+        SPI: spi_type::spi::SpiDevice<u8>,
+    {
+        // `SPI_RD_CRC` is read directly, as a regular read would check the CRC
+        // of this read again
+        let mut r = spi_rd_crc::R([0; SPI_RD_CRC::HEADER_LEN + SPI_RD_CRC::LEN]);
+        init_header::<SPI_RD_CRC>(false, &mut r.0);
+        self.spi
+            .transfer_in_place(&mut r.0)
+            .await
+            .map_err(Error::Transfer)?;
+
+        let actual = r.value();
+        if actual != expected {
+            return Err(Error::Crc { expected, actual });
+        }
+
+        Ok(())
+    }
+
+    /// Reads from the register described by `register`
+    ///
+    /// Reads `data.len()` bytes, starting at the register's address. This is
+    /// meant for tools that walk the register map, the typed accessors are
+    /// preferable otherwise.
+    #[maybe_async_attr]
+    pub async fn read_register(
+        &mut self,
+        register: &RegisterInfo,
+        data: &mut [u8],
+    ) -> Result<(), Error<SPI>>
+    where
+        SPI: spi_type::spi::SpiDevice<u8>,
+    {
+        let header = header(false, register.id, register.sub_id);
+        self.spi
+            .transaction(&mut [Operation::Write(&header), Operation::Read(data)])
+            .await
+            .map_err(Error::Transfer)?;
+
+        if self.crc {
+            let crc = crc8(crc8(0, &header), data);
+            self.check_read_crc(crc).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Calculates the CRC-8 used in SPI CRC mode
+///
+/// The CRC uses the polynomial `x^8 + x^2 + x + 1` (`0x07`), without
+/// reflection or final XOR. `crc` is the initial value, which allows
+/// calculating the CRC over several slices.
+pub fn crc8(mut crc: u8, data: &[u8]) -> u8 {
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
 }
 
 /// Provides access to a register
@@ -81,13 +199,20 @@ where
         let mut r = R::read();
         let buffer = R::buffer(&mut r);
 
-        init_header::<R>(false, buffer);
+        let header_len = init_header::<R>(false, buffer);
+        // The header is overwritten by the transfer, so start the CRC now
+        let crc = crc8(0, &buffer[..header_len]);
         self.0
             .spi
             .transfer_in_place(buffer)
             .await
             .map_err(Error::Transfer)?;
 
+        if self.0.crc {
+            let crc = crc8(crc, &buffer[header_len..]);
+            self.0.check_read_crc(crc).await?;
+        }
+
         Ok(r)
     }
 
@@ -105,9 +230,7 @@ where
         let buffer = R::buffer(&mut w);
         init_header::<R>(true, buffer);
 
-        SPI::write(&mut self.0.spi, buffer)
-            .await
-            .map_err(Error::Transfer)?;
+        self.0.write_frame(buffer).await?;
 
         Ok(())
     }
@@ -130,9 +253,64 @@ where
         let buffer = <R as Writable>::buffer(&mut w);
         init_header::<R>(true, buffer);
 
-        SPI::write(&mut self.0.spi, buffer)
-            .await
-            .map_err(Error::Transfer)?;
+        self.0.write_frame(buffer).await?;
+
+        Ok(())
+    }
+
+    /// Modify the register with a masked write
+    ///
+    /// Works like [`RegAccessor::modify`], but instead of reading the
+    /// register and writing it back, the bits set by `f` are sent as an AND
+    /// and an OR mask in a single transaction, which the DW3000 applies
+    /// itself. This halves the SPI traffic and can't overwrite bits that the
+    /// DW3000 changes in the meantime.
+    ///
+    /// `f` is called twice, to find the bits it sets. Only the fields set by
+    /// `f` are changed. If those don't fit into a 1, 2 or 4 byte window within
+    /// the register, this falls back to [`RegAccessor::modify`].
+    #[inline]
+    #[maybe_async_attr]
+    pub async fn modify_masked<F>(&mut self, f: F) -> Result<(), Error<SPI>>
+    where
+        R: Register + Readable + Writable,
+        F: Fn(&mut R::Write) -> &mut R::Write,
+    {
+        // Bits that `f` sets to 1 end up in the OR mask, bits it sets to 0
+        // end up in the AND mask
+        let mut or_mask = R::write();
+        f(&mut or_mask);
+        let mut and_mask = R::write();
+        <R as Writable>::buffer(&mut and_mask).fill(0xff);
+        f(&mut and_mask);
+
+        let or_mask = &<R as Writable>::buffer(&mut or_mask)[2..];
+        let and_mask = &<R as Writable>::buffer(&mut and_mask)[2..];
+
+        let mut touched = (0..R::LEN).filter(|&i| and_mask[i] != 0xff || or_mask[i] != 0);
+        let Some(first) = touched.next() else {
+            return Ok(());
+        };
+        let last = touched.next_back().unwrap_or(first);
+
+        let (mode, width) = match last - first + 1 {
+            1 => (0b01, 1),
+            2 => (0b10, 2),
+            3 | 4 => (0b11, 4),
+            _ => return self.modify(|_, w| f(w)).await,
+        };
+        if width > R::LEN {
+            return self.modify(|_, w| f(w)).await;
+        }
+        let start = first.min(R::LEN - width);
+
+        let mut buffer = [0; 2 + 2 * 4];
+        buffer[..2].copy_from_slice(&header(true, R::ID, R::SUB_ID + start as u8));
+        buffer[1] |= mode;
+        buffer[2..2 + width].copy_from_slice(&and_mask[start..start + width]);
+        buffer[2 + width..2 + 2 * width].copy_from_slice(&or_mask[start..start + width]);
+
+        self.0.write_frame(&buffer[..2 + 2 * width]).await?;
 
         Ok(())
     }
@@ -145,6 +323,14 @@ where
 {
     /// SPI error occured during a transfer transaction
     Transfer(SPI::Error),
+
+    /// The CRC of a read didn't match `SPI_RD_CRC` in SPI CRC mode
+    Crc {
+        /// The CRC calculated over the received data
+        expected: u8,
+        /// The CRC the DW3000 calculated over the data it sent
+        actual: u8,
+    },
 }
 
 impl<SPI> Display for Error<SPI>
@@ -170,6 +356,13 @@ where
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Transfer(error) => write!(f, "Transfer({:?})", error),
+            Error::Crc { expected, actual } => {
+                write!(
+                    f,
+                    "Crc {{ expected: {:?}, actual: {:?} }}",
+                    expected, actual
+                )
+            }
         }
     }
 }
@@ -182,6 +375,14 @@ where
     fn format(&self, f: defmt::Formatter) {
         match self {
             Error::Transfer(_) => defmt::write!(f, "Transfer()"),
+            Error::Crc { expected, actual } => {
+                defmt::write!(
+                    f,
+                    "Crc {{ expected: {:?}, actual: {:?} }}",
+                    expected,
+                    actual
+                )
+            }
         }
     }
 }
@@ -193,20 +394,122 @@ where
 /// header that was written.
 ///
 /// TODO: Here we always use the full address, but we should also support the
-/// short address mode and masked write mode.
+/// short address mode.
 #[inline(always)]
 fn init_header<R: Register>(write: bool, buffer: &mut [u8]) -> usize {
+    buffer[..2].copy_from_slice(&header(write, R::ID, R::SUB_ID));
+
+    2
+}
+
+/// Returns the 2-octet SPI message header for the given register address
+#[inline(always)]
+fn header(write: bool, id: u8, sub_id: u8) -> [u8; 2] {
     // bool write defines if we are in read or write mode (first bit)
     // sub_id is a bool that defines if we are in full or short command
     // we start with full address!
-    buffer[0] = (((write as u8) << 7) & 0x80)
-        | (1u8 << 6) // We always use 2-octet addressing
-        | ((R::ID << 1) & 0x3e) // 5-bit base address
-        | ((R::SUB_ID >> 6) & 0x01); // MSB of the 7-bit sub-address
+    [
+        (((write as u8) << 7) & 0x80)
+            | (1u8 << 6) // We always use 2-octet addressing
+            | ((id << 1) & 0x3e) // 5-bit base address
+            | ((sub_id >> 6) & 0x01), // MSB of the 7-bit sub-address
+        sub_id << 2, // last two bits M1 M0 select masked writes
+    ]
+}
 
-    buffer[1] = R::SUB_ID << 2; // last two bits M1 M0 are always 0
+/// The access mode of a register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub enum Access {
+    /// Read-only
+    RO,
+    /// Readable and writable
+    RW,
+}
 
-    2
+/// Describes a register declared in the register map
+///
+/// The descriptions of all registers are available in [`REGISTERS`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub struct RegisterInfo {
+    /// The register's name, as in the user manual
+    pub name: &'static str,
+    /// The register index
+    pub id: u8,
+    /// The register's sub-index
+    pub sub_id: u8,
+    /// The length of the register in bytes
+    pub len: usize,
+    /// The register's access mode
+    pub access: Access,
+    /// The register's fields
+    pub fields: &'static [FieldInfo],
+    /// The register's value after reset, if it is declared in the register
+    /// map
+    pub reset: Option<u128>,
+}
+
+/// Describes a field of a register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub struct FieldInfo {
+    /// The field's name
+    pub name: &'static str,
+    /// The index of the field's first bit
+    pub first_bit: usize,
+    /// The index of the field's last bit
+    pub last_bit: usize,
+}
+
+impl RegisterInfo {
+    /// Returns the register with the given name, e.g. `"SYS_CFG"`
+    pub fn by_name(name: &str) -> Option<&'static RegisterInfo> {
+        REGISTERS.iter().find(|register| register.name == name)
+    }
+
+    /// Returns the register at the given address
+    ///
+    /// A few address ranges are covered by more than one register, for
+    /// example `DB_DIAG` and `DB_DIAG_SET1`. In that case, the register that
+    /// is declared first is returned.
+    pub fn by_address(id: u8, sub_id: u8) -> Option<&'static RegisterInfo> {
+        REGISTERS
+            .iter()
+            .find(|register| register.id == id && register.sub_id == sub_id)
+    }
+
+    /// Returns the field with the given name
+    pub fn field(&self, name: &str) -> Option<&'static FieldInfo> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns the reset value as register data, least significant byte
+    /// first, if it is declared
+    ///
+    /// Only the first [`len`](Self::len) bytes belong to the register.
+    pub fn reset_data(&self) -> Option<[u8; 16]> {
+        self.reset.map(u128::to_le_bytes)
+    }
+}
+
+impl FieldInfo {
+    /// Extracts the field's value from the register data
+    ///
+    /// `data` holds the register's bytes, least significant byte first. Bits
+    /// above 127 and beyond the end of `data` are ignored.
+    pub fn value(&self, data: &[u8]) -> u128 {
+        let mut value = 0;
+
+        for (i, bit) in (self.first_bit..=self.last_bit).take(128).enumerate() {
+            let set = data
+                .get(bit / 8)
+                .is_some_and(|byte| byte >> (bit % 8) & 1 == 1);
+            value |= (set as u128) << i;
+        }
+
+        value
+    }
 }
 
 /// Implemented for all registers
@@ -268,7 +571,7 @@ macro_rules! impl_register {
             $sub_id:expr,
             $len:expr,
             $rw:tt,
-            $name:ident($name_lower:ident) {
+            $name:ident($name_lower:ident) $(= $reset:literal)? {
             #[$doc:meta]
             $(
                 $field:ident,
@@ -567,11 +870,37 @@ macro_rules! impl_register {
         impl<SPI> DW3000<SPI> {
             $(
                 #[$doc]
-                pub fn $name_lower(&mut self) -> RegAccessor<$name, SPI> {
+                pub fn $name_lower(&mut self) -> RegAccessor<'_, $name, SPI> {
                     RegAccessor(self, PhantomData)
                 }
             )*
         }
+
+        /// All registers declared in the register map, in declaration order
+        ///
+        /// The buffers that are implemented by hand, like `TX_BUFFER` and the
+        /// RX buffers, are not included.
+        pub const REGISTERS: &[RegisterInfo] = &[
+            $(
+                RegisterInfo {
+                    name: stringify!($name),
+                    id: $id,
+                    sub_id: $sub_id,
+                    len: $len,
+                    access: Access::$rw,
+                    fields: &[
+                        $(
+                            FieldInfo {
+                                name: stringify!($field),
+                                first_bit: $first_bit,
+                                last_bit: $last_bit,
+                            },
+                        )*
+                    ],
+                    reset: reset_value!($($reset)?),
+                },
+            )*
+        ];
     }
 }
 
@@ -613,9 +942,19 @@ macro_rules! impl_rw {
     };
 }
 
+// Helper macro, used internally by `impl_register!`
+macro_rules! reset_value {
+    () => {
+        None
+    };
+    ($reset:literal) => {
+        Some($reset)
+    };
+}
+
 // All register are implemented in this macro invocation. It follows the
 // following syntax:
-// <id>, <sub-id>, <size-bytes>, <RO/RW>, <name-upper>(name-lower) { /// <doc>
+// <id>, <sub-id>, <size-bytes>, <RO/RW>, <name-upper>(name-lower) [= <reset>] { /// <doc>
 //     <field 1>
 //     <field 2>
 //     ...
@@ -627,7 +966,7 @@ macro_rules! impl_rw {
 /************************************************************************ */
 // registers for DWM3000
 // Each field follows the following syntax:
-// <Id>, <Offset>, <Length>, <Access>, <NAME(name)>
+// <Id>, <Offset>, <Length>, <Access>, <NAME(name)> [= <reset value>]
 //      <name>, <first-bit-index>, <last-bit-index>, <type>; /// <doc>
 
 impl_register! {
@@ -641,7 +980,7 @@ impl_register! {
     0x00, 0x04, 8, RW, EUI(eui) { /// Extended Unique Identifier
         value, 0, 63, u64; /// Extended Unique Identifier
     }
-    0x00, 0x0C, 4, RW, PANADR(panadr) { /// PAN Identifier and Short Address
+    0x00, 0x0C, 4, RW, PANADR(panadr) = 0xFFFF_FFFF { /// PAN Identifier and Short Address
         short_addr,  0, 15, u16; /// Short Address
         pan_id,     16, 31, u16; /// PAN Identifier
     }
@@ -695,19 +1034,20 @@ impl_register! {
         txb_offset, 16, 25, u16; /// Transmit buffer index offset
         fine_plen,  40, 47, u8; /// Fine PSR control
     }
-    0x00, 0x2C, 4, RW, DX_TIME(dx_time) { /// Delayed Send or Receive Time
+    0x00, 0x2C, 4, RW, DX_TIME(dx_time) = 0 { /// Delayed Send or Receive Time
         value, 0, 31, u32; /// Delayed Send or Receive Time
     }
-    0x00, 0x30, 4, RW, DREF_TIME(dref_time) { ///  Delayed send or receive reference time
+    0x00, 0x30, 4, RW, DREF_TIME(dref_time) = 0 { ///  Delayed send or receive reference time
         value, 0, 31, u32; /// Delayed send or receive reference time
     }
-    0x00, 0x34, 3, RW, RX_FWTO(rx_fwto) { /// Receive frame wait timeout period
+    0x00, 0x34, 3, RW, RX_FWTO(rx_fwto) = 0 { /// Receive frame wait timeout period
         value, 0, 23, u32; /// Receive frame wait timeout period
     }
     0x00, 0x38, 1, RW, SYS_CTRL(sys_ctrl) { /// System Control Register
         value, 0, 7, u8; /// System control
     }
     0x00, 0x3C, 6, RW, SYS_ENABLE(sys_enable) { /// System event enable mask register
+        value,          0, 47, u64; /// All event masks
         cplock_en,      1,  1, u8; /// Mask clock PLL lock event
         spicrce_en,     2,  2, u8; /// Mask SPI CRC Error event
         aat_en,         3,  3, u8; /// Mask automatic acknowledge trigger event
@@ -748,6 +1088,7 @@ impl_register! {
         cca_fail_en,   44,  44, u8; /// Mask CCA fail interrupt event
     }
     0x00, 0x44, 6, RW, SYS_STATUS(sys_status) { /// System Event Status Register
+        value,      0, 47, u64; /// All events
         irqs,       0,  0, u8; /// Interrupt Request Status
         cplock,     1,  1, u8; /// Clock PLL Lock
         spicrce,    2,  2, u8; /// External Sync Clock Reset
@@ -777,16 +1118,16 @@ impl_register! {
         hpdwarn,   27, 27, u8; /// Half Period Delay Warning
         cperr,     28, 28, u8; /// Scramble Timestamp Sequence (STS) error
         arfe,      29, 29, u8; /// Automatic Frame Filtering rejection
-        rxprej,    29, 29, u8; /// Receiver Preamble Rejection
-        vt_det,    33, 33, u8; /// Voltage or temperature variation detected
-        gpioirq,   36, 36, u8; /// GPIO interrupt
-        aes_done,  37, 37, u8; /// AES-DMA operation complete
-        aes_err,   38, 38, u8; /// AES-DMA error
-        cmd_err,   39, 39, u8; /// Command error
-        spi_ovf,   40, 40, u8; /// SPI overflow error
-        spi_unf,   41, 41, u8; /// SPI underflow error
-        spierr,    42, 42, u8; /// SPI collision error
-        cca_fail,  43, 43, u8; /// This event will be set as a result of failure of CMD_CCA_TX to transmit a packet
+        rxprej,    33, 33, u8; /// Receiver Preamble Rejection
+        vt_det,    36, 36, u8; /// Voltage or temperature variation detected
+        gpioirq,   37, 37, u8; /// GPIO interrupt
+        aes_done,  38, 38, u8; /// AES-DMA operation complete
+        aes_err,   39, 39, u8; /// AES-DMA error
+        cmd_err,   40, 40, u8; /// Command error
+        spi_ovf,   41, 41, u8; /// SPI overflow error
+        spi_unf,   42, 42, u8; /// SPI underflow error
+        spierr,    43, 43, u8; /// SPI collision error
+        cca_fail,  44, 44, u8; /// This event will be set as a result of failure of CMD_CCA_TX to transmit a packet
     }
     0x00, 0x4C, 4, RO, RX_FINFO(rx_finfo) { /// RX Frame Information
         rxflen,  0,  9, u16; /// Receive Frame Length
@@ -812,11 +1153,11 @@ impl_register! {
     0x01, 0x04, 2, RW, TX_ANTD(tx_antd) { /// Transmitter antenna delay
         value, 0, 15, u16; /// Transmitter antenna delay
     }
-    0x01, 0x08, 4, RW, ACK_RESP(ack_resp) { /// Acknowledgement delay time and response time
+    0x01, 0x08, 4, RW, ACK_RESP(ack_resp) = 0 { /// Acknowledgement delay time and response time
         w4r_tim,  0, 19, u32; /// Wait-for-Response turn-around Time
         ack_tim,  24, 31, u8; /// Auto-Acknowledgement turn-around TimeC
     }
-    0x01, 0x0C, 4, RW, TX_POWER(tx_power) { /// TX Power Control
+    0x01, 0x0C, 4, RW, TX_POWER(tx_power) = 0xFEFE_FEFE { /// TX Power Control
         value, 0, 31, u32; /// TX Power Control value
     }
     0x01, 0x14, 2, RW, CHAN_CTRL(chan_ctrl) { /// Channel Control Register
@@ -833,7 +1174,7 @@ impl_register! {
         le_addr2,  0, 15, u16; /// Low Energy device 16-bit address
         le_addr3, 16, 31, u16; /// Low Energy device 16-bit address
     }
-    0x01, 0x20, 1, RW, SPI_COLLISION(spi_collision) { /// SPI collision status
+    0x01, 0x20, 1, RW, SPI_COLLISION(spi_collision) = 0 { /// SPI collision status
         value,  0, 7, u8; /// SPI collision status
     }
     0x01, 0x24, 1, RW, RDB_STATUS(rdb_status) { /// RX double buffer status
@@ -846,7 +1187,7 @@ impl_register! {
         ciadone1,   6, 6, u8; /// CIA processing done on the CIR relating to a message in RX_BUFFER_1 when operating in double buffer mode
         cp_err1,    7, 7, u8; /// Scramble Timestamp Sequence (STS) error
     }
-    0x01, 0x28, 1, RW, RDB_DIAG(rdb_diag) { /// RX double buffer diagnostic configuration
+    0x01, 0x28, 1, RW, RDB_DIAG(rdb_diag) = 0 { /// RX double buffer diagnostic configuration
         rdb_dmode,    0, 2, u8; /// RX double buffer diagnostic mode
     }
     0x01, 0x30, 2, RW, AES_CFG(aes_cfg) { /// AES configuration
@@ -981,7 +1322,8 @@ impl_register! {
     /*******************************************************************/
     /*****************    GPIO_CTRL REGISTER   *************************/
     /*******************************************************************/
-    0x05, 0x00, 4, RW, GPIO_MODE(gpio_mode) { /// GPIO Mode Control Register
+    0x05, 0x00, 4, RW, GPIO_MODE(gpio_mode) = 0 { /// GPIO Mode Control Register
+        value,   0, 26, u32; ///   Mode selection of all pins
         msgp0,  0,  2, u8; ///  Mode Selection for GPIO0/RXOKLED
         msgp1,  3,  5, u8; ///  Mode Selection for GPIO1/SFDLED
         msgp2,  6,  8, u8; ///  Mode Selection for GPIO2/RXLED
@@ -993,6 +1335,7 @@ impl_register! {
         msgp8,  24,  26, u8; ///  Mode Selection for GPIO8
     }
     0x05, 0x04, 2, RW, GPIO_PULL_EN(gpio_pull_en) { /// GPIO Drive Strength and Pull Control
+        value,   0,  8, u16; ///   Drive strength of all pins
         mgpen0,  0,  0, u8; ///  Setting to 0 will lower the drive strength
         mgpen1,  1,  1, u8; ///  Setting to 0 will lower the drive strength
         mgpen2,  2,  2, u8; ///  Setting to 0 will lower the drive strength
@@ -1004,6 +1347,7 @@ impl_register! {
         mgpen8,  8,  8, u8; ///  Setting to 0 will lower the drive strength
     }
     0x05, 0x08, 2, RW, GPIO_DIR(gpio_dir) { /// GPIO Direction Control Register
+        value,   0,  8, u16; ///   Direction of all pins
         gpd0,  0,  0, u8; ///   value of 0 means the pin is an output
         gpd1,  1,  1, u8; ///   value of 0 means the pin is an output
         gpd2,  2,  2, u8; ///   value of 0 means the pin is an output
@@ -1014,7 +1358,8 @@ impl_register! {
         gpd7,  7,  7, u8; ///   value of 0 means the pin is an output
         gpd8,  8,  8, u8; ///   value of 0 means the pin is an output
     }
-    0x05, 0x0C, 2, RW, GPIO_OUT(gpio_out) { /// GPIO Data Output Register
+    0x05, 0x0C, 2, RW, GPIO_OUT(gpio_out) = 0 { /// GPIO Data Output Register
+        value,   0,  8, u16; ///   Output of all pins
         gop0,  0,  0, u8; ///   show the current output setting
         gop1,  1,  1, u8; ///   show the current output setting
         gop2,  2,  2, u8; ///   show the current output setting
@@ -1026,6 +1371,7 @@ impl_register! {
         gop8,  8,  8, u8; ///   show the current output setting
     }
     0x05, 0x10, 2, RW, GPIO_IRQE(gpio_irqe) { /// GPIO Interrupt Enable
+        value,   0,  8, u16; ///   Interrupt enable of all pins
         girqe0,  0,  0, u8; ///   selected as interrupt source
         girqe1,  1,  1, u8; ///   selected as interrupt source
         girqe2,  2,  2, u8; ///   selected as interrupt source
@@ -1037,6 +1383,7 @@ impl_register! {
         girqe8,  8,  8, u8; ///   selected as interrupt source
     }
     0x05, 0x14, 2, RW, GPIO_ISTS(gpio_ists) { /// GPIO Interrupt Status
+        value,   0,  8, u16; ///   Interrupt status of all pins
         gists0,  0,  0, u8; ///   Value 1 means GPIO gave rise to the GPIOIRQ SYS_STATUS event
         gists1,  1,  1, u8; ///   Value 1 means GPIO gave rise to the GPIOIRQ SYS_STATUS event
         gists2,  2,  2, u8; ///   Value 1 means GPIO gave rise to the GPIOIRQ SYS_STATUS event
@@ -1048,6 +1395,7 @@ impl_register! {
         gists8,  8,  8, u8; ///   Value 1 means GPIO gave rise to the GPIOIRQ SYS_STATUS event
     }
     0x05, 0x18, 2, RW, GPIO_ISEN(gpio_isen) { /// GPIO Interrupt Sense Selection
+        value,   0,  8, u16; ///   Interrupt sense of all pins
         gisen0,  0,  0, u8; ///   GPIO IRQ Sense selection GPIO input
         gisen1,  1,  1, u8; ///   GPIO IRQ Sense selection GPIO input
         gisen2,  2,  2, u8; ///   GPIO IRQ Sense selection GPIO input
@@ -1059,6 +1407,7 @@ impl_register! {
         gisen8,  8,  8, u8; ///   GPIO IRQ Sense selection GPIO input
     }
     0x05, 0x1C, 2, RW, GPIO_IMODE(gpio_imode) { /// GPIO Interrupt Mode (Level / Edge)
+        value,   0,  8, u16; ///   Interrupt mode of all pins
         gimod0,  0,  0, u8; ///   GPIO IRQ Mode selection for GPIO input
         gimod1,  1,  1, u8; ///   GPIO IRQ Mode selection for GPIO input
         gimod2,  2,  2, u8; ///   GPIO IRQ Mode selection for GPIO input
@@ -1070,6 +1419,7 @@ impl_register! {
         gimod8,  8,  8, u8; ///   GPIO IRQ Mode selection for GPIO input
     }
     0x05, 0x20, 2, RW, GPIO_IBES(gpio_ibes) { /// GPIO Interrupt “Both Edge” Select
+        value,   0,  8, u16; ///   Both edge selection of all pins
         gibes0,  0,  0, u8; ///   GPIO IRQ “Both Edge” selection for GPIO input
         gibes1,  1,  1, u8; ///   GPIO IRQ “Both Edge” selection for GPIO input
         gibes2,  2,  2, u8; ///   GPIO IRQ “Both Edge” selection for GPIO input
@@ -1081,6 +1431,7 @@ impl_register! {
         gibes8,  8,  8, u8; ///   GPIO IRQ “Both Edge” selection for GPIO input
     }
     0x05, 0x24, 4, RW, GPIO_ICLR(gpio_iclr) { /// GPIO Interrupt Latch Clear
+        value,   0,  8, u16; ///   Interrupt latch clear of all pins
         giclr0,  0,  0, u8; ///   GPIO IRQ latch clear for GPIO input
         giclr1,  1,  1, u8; ///   GPIO IRQ latch clear for GPIO input
         giclr2,  2,  2, u8; ///   GPIO IRQ latch clear for GPIO input
@@ -1092,6 +1443,7 @@ impl_register! {
         giclr8,  8,  8, u8; ///   GPIO IRQ latch clear for GPIO input
     }
     0x05, 0x28, 4, RW, GPIO_IDBE(gpio_idbe) { /// GPIO Interrupt De-bounce Enable
+        value,   0,  8, u16; ///   De-bounce enable of all pins
         gidbe0,  0,  0, u8; ///   GPIO IRQ de-bounce enable for GPIO
         gidbe1,  1,  1, u8; ///   GPIO IRQ de-bounce enable for GPIO
         gidbe2,  2,  2, u8; ///   GPIO IRQ de-bounce enable for GPIO
@@ -1103,6 +1455,7 @@ impl_register! {
         gidbe8,  8,  8, u8; ///   GPIO IRQ de-bounce enable for GPIO
     }
     0x05, 0x2C, 2, RO, GPIO_RAW(gpio_raw) { /// GPIO Raw State
+        value,   0,  8, u16; ///   Raw state of all pins
         grawp0,  0,  0, u8; ///   GPIO port raw state
         grawp1,  1,  1, u8; ///   GPIO port raw state
         grawp2,  2,  2, u8; ///   GPIO port raw state
@@ -1127,7 +1480,7 @@ impl_register! {
     0x06, 0x04, 2, RW, PRE_TOC(pre_toc) { /// Preamble detection timeout
         value,  0,  15, u16; /// digital receiver configuration
     }
-    0x06, 0x0C, 4, RW, DTUNE3(dtune3) { /// Receiver tuning register
+    0x06, 0x0C, 4, RW, DTUNE3(dtune3) = 0xAF5F_584C { /// Receiver tuning register
         value,  0,  31, u32; /// value
     }
     0x06, 0x10, 4, RW, DTUNE4(dtune4) { /// Digital Tuning Reserved register
@@ -1137,19 +1490,20 @@ impl_register! {
         value,  0,  31, u32; /// value
     }
     0x06, 0x29, 3, RO, DRX_CAR_INT(drx_car_int) { /// Carrier recovery integrator register
-        value,  0,  23, u32; /// value
+        value,  0,  20, u32; /// value
     }
 
     /*******************************************************************/
     /*****************     RF_CONF REGISTER    *************************/
     /*******************************************************************/
-    0x07, 0x00, 4, RW, RF_ENABLE(rf_enable) { /// RF control enable
+    0x07, 0x00, 4, RW, RF_ENABLE(rf_enable) = 0 { /// RF control enable
         value,  0,  31, u32; /// value
     }
-    0x07, 0x04, 4, RW, RF_CTRL_MASK(rf_ctrl_mask) { /// RF enable mask
+    0x07, 0x04, 4, RW, RF_CTRL_MASK(rf_ctrl_mask) = 0 { /// RF enable mask
         value,  0,  31, u32; /// value
     }
     0x07, 0x14, 4, RW, RF_SWITCH(rf_switch) { /// RF switch configuration
+        value,          0, 31, u32; /// value
         antswnotoggle,  0,  0, u8; /// When set to 1, the automatic toggling of the antenna switch is disabled when the device is operating in PDoA modes
         antswpdoaport,  1,  1, u8; /// Specifies the starting port for reception when the device is operating in PDoA modes
         antswen,        8,  8, u8; /// Setting this to 1 will enable manual control of the antenna switch
@@ -1163,7 +1517,7 @@ impl_register! {
     0x07, 0x1C, 4, RW, RF_TX_CTRL_2(rf_tx_ctrl_2) { /// RF transmitter configuration
         value,  0,  31, u32; /// Pulse Generator Delay value
     }
-    0x07, 0x28, 1, RW, TX_TEST(tx_test) { /// Transmitter test configuration
+    0x07, 0x28, 1, RW, TX_TEST(tx_test) = 0 { /// Transmitter test configuration
         tx_entest,  0,  3, u8; /// Transmitter test enable
     }
     0x07, 0x34, 1, RW, SAR_TEST(rsar_test) { /// Transmitter Calibration – SAR temperaturesensor read enable
@@ -1172,6 +1526,10 @@ impl_register! {
     0x07, 0x40, 8, RW, LDO_TUNE(ldo_tune) { /// Internal LDO voltage tuning parameter
         value,  0x00,  0x3C, u128; ///  used to control the output voltage levels of the on chip LDOs
     }
+    0x07, 0x44, 4, RW, LDO_TUNE_HI(ldo_tune_hi) { /// Upper word of LDO_TUNE
+        value,           0, 31, u32; /// Upper word of LDO_TUNE
+        ldo_hvaux_tune, 12, 15, u8;  /// HVAUX LDO output voltage tuning
+    }
     0x07, 0x48, 4, RW, LDO_CTRL(ldo_ctrl) { /// LDO control
         low,  0,  15, u16; ///  LDO control
         high, 16,  31, u16; ///  LDO control
@@ -1209,7 +1567,7 @@ impl_register! {
     0x08, 0x18, 2, RW, PG_TEST(pg_test) { /// Transmitter Calibration – Pulse Generator test
         value, 0, 15, u16; /// Pulse Generator test
     }
-    0x08, 0x1C, 2, RO, PG_CAL_TARGET(pg_cal_target) { /// Transmitter Calibration – Pulse Generator count target value
+    0x08, 0x1C, 2, RW, PG_CAL_TARGET(pg_cal_target) { /// Transmitter Calibration – Pulse Generator count target value
         value, 0, 11, u16; /// Pulse generator target value of PG_COUNT at which point PG auto cal will complete.
     }
 
@@ -1321,7 +1679,7 @@ impl_register! {
         sts1_toast, 55,  63, u16; /// STS second Time of Arrival status indicator.
     }
     0x0C, 0x18, 6, RO, TDOA(tdoa) { /// The TDoA between the two CIRs
-        value, 0, 47, u64; /// The TDoA between the two CIRs
+        value, 0, 40, u64; /// The TDoA between the two CIRs
     }
     0x0C, 0x1E, 2, RO, PDOA(pdoa) { /// The PDoA between the two CIRs
         pdoa,      0, 13, u16; /// Phase difference result.
@@ -1444,13 +1802,13 @@ impl_register! {
         sts_pgr_en,   31, 31, u8; /// Test the growth rate of the STS based CIR to the earlier growth rate of the preamble based CIR.
     }
     0x0E, 0x1A, 2, RW, CIA_ADJUST(cia_adjust) { /// User adjustment to the PDoA
-        value, 0, 13, u8; /// Adjustment value to account for non-balanced antenna circuits.
+        value, 0, 13, u16; /// Adjustment value to account for non-balanced antenna circuits.
     }
 
     /*******************************************************************/
     /*****************     DIG_DIAG REGISTER    ************************/
     /*******************************************************************/
-    0x0F, 0x00, 1, RW, EVC_CTRL(evc_ctrl) { /// Event counter control
+    0x0F, 0x00, 1, RW, EVC_CTRL(evc_ctrl) = 0 { /// Event counter control
         evc_en,  0, 0, u8; /// Event Counters Enable.
         evc_clr, 1, 1, u8; /// Event Counters Clear.
     }
@@ -1493,7 +1851,7 @@ impl_register! {
     0x0F, 0x1C, 8, RO, EVC_RES1(evc_res1) { /// Digital diagnostics reserved area 1
         value, 0, 63, u64; /// Digital diagnostics reserved area 1
     }
-    0x0F, 0x24, 4, RW, DIAG_TMC(diag_tmc) { /// Test mode control register
+    0x0F, 0x24, 4, RW, DIAG_TMC(diag_tmc) = 0 { /// Test mode control register
         tx_pstm,    4,  4, u8; /// Transmit Power Spectrum Test Mode.
         hirq_pol,  21, 21, u8; /// Host interrupt polarity.
         cia_wden,  24, 24, u8; /// Enable the CIA watchdog.
@@ -1559,21 +1917,31 @@ impl_register! {
         force2init,   23, 23, u8; /// Force to IDLE_RC state.
         lp_clk_div,   26, 31, u8; /// Kilohertz clock divisor.
     }
-    0x11, 0x12, 4, RW, TXFSEQ(txfseq) { /// PMSC fine grain TX sequencing control
+    0x11, 0x12, 4, RW, TXFSEQ(txfseq) = 0x04D2_8874 { /// PMSC fine grain TX sequencing control
         value, 0, 31, u32; /// PMSC fine grain TX sequencing control
     }
-    0x11, 0x16, 4, RW, LED_CTRL(led_ctrl) { /// PMSC fine grain TX sequencing control
+    0x11, 0x16, 4, RW, LED_CTRL(led_ctrl) = 0x20 { /// PMSC fine grain TX sequencing control
         blink_tim,   0,  7, u8; /// Blink time count value.
         blink_en,    8,  8, u8; /// Blink Enable.
         force_trig, 16, 19, u8; /// Manually triggers an LED blink.
     }
-    0x11, 0x1A, 4, RW, RX_SNIFF(rx_sniff) { /// Receiver SNIFF mode configuration
+    0x11, 0x1A, 4, RW, RX_SNIFF(rx_sniff) = 0 { /// Receiver SNIFF mode configuration
         sniff_on,   0,  3, u8; /// SNIFF Mode ON time.
         sniff_off,  8, 15, u8; /// SNIFF Mode OFF time specified in μs.
     }
     0x11, 0x1F, 2, RW, BIAS_CTRL(bias_ctrl) { /// Analog blocks’ calibration values
         value, 0, 13, u16; /// Analog blocks’ calibration values
     }
+    // The public DW3000 user manual doesn't document this register. The
+    // address and bits follow Qorvo's `dwt_ds_sema_request`,
+    // `dwt_ds_sema_release` and `dwt_ds_sema_status` from the DW3xxx API, and
+    // haven't been verified on hardware.
+    0x11, 0x24, 2, RW, SPI_SEM(spi_sem) { /// SPI semaphore control and status
+        req,   0, 0, u8; /// Requests the semaphore for the accessing SPI port
+        rel,   1, 1, u8; /// Releases the semaphore held by the accessing SPI port
+        spi1,  8, 8, u8; /// The semaphore is held by SPI1
+        spi2,  9, 9, u8; /// The semaphore is held by SPI2
+    }
 
     /*******************************************************************/
     /*****************     ACC_MEM REGISTER    *************************/
@@ -1629,6 +1997,7 @@ impl_register! {
     /*****************     IN_PTR_CFG REGISTER    **********************/
     /*******************************************************************/
     0x1F, 0x00, 1, RO, FINT_STAT(fint_stat) { /// Fast System Event Status Register
+        value,      0,  7,  u8; /// All fast events
         txok,       0,  0,  u8; /// TXFRB or TXPRS or TXPHS or TXFRS.
         cca_fail,   1,  1,  u8; /// AAT or CCA_FAIL.
         rxtserr,    2,  2,  u8; /// CIAERR
@@ -1679,7 +2048,7 @@ impl Writable for TX_BUFFER {
 
 impl<SPI> DW3000<SPI> {
     /// Transmit Data Buffer
-    pub fn tx_buffer(&mut self) -> RegAccessor<TX_BUFFER, SPI> {
+    pub fn tx_buffer(&mut self) -> RegAccessor<'_, TX_BUFFER, SPI> {
         RegAccessor(self, PhantomData)
     }
 }
@@ -1728,7 +2097,7 @@ impl Readable for RX_BUFFER_0 {
 
 impl<SPI> DW3000<SPI> {
     /// Receive Data Buffer
-    pub fn rx_buffer_0(&mut self) -> RegAccessor<RX_BUFFER_0, SPI> {
+    pub fn rx_buffer_0(&mut self) -> RegAccessor<'_, RX_BUFFER_0, SPI> {
         RegAccessor(self, PhantomData)
     }
 }
@@ -1789,7 +2158,7 @@ impl Readable for RX_BUFFER_1 {
 
 impl<SPI> DW3000<SPI> {
     /// Receive Data Buffer1
-    pub fn rx_buffer_1(&mut self) -> RegAccessor<RX_BUFFER_1, SPI> {
+    pub fn rx_buffer_1(&mut self) -> RegAccessor<'_, RX_BUFFER_1, SPI> {
         RegAccessor(self, PhantomData)
     }
 }
@@ -1878,3 +2247,114 @@ impl_bytes! {
     u64,
     u128,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    #[test]
+    fn test_crc8() {
+        assert_eq!(crc8(0, b"123456789"), 0xF4);
+        assert_eq!(crc8(crc8(0, b"1234"), b"56789"), 0xF4);
+    }
+
+    #[test]
+    fn test_register_info() {
+        let sys_cfg = RegisterInfo::by_name("SYS_CFG").unwrap();
+        assert_eq!((sys_cfg.id, sys_cfg.sub_id, sys_cfg.len), (0x00, 0x10, 4));
+        assert_eq!(sys_cfg.access, Access::RW);
+        assert_eq!(RegisterInfo::by_address(0x00, 0x10), Some(sys_cfg));
+
+        let spi_crcen = sys_cfg.field("spi_crcen").unwrap();
+        assert_eq!((spi_crcen.first_bit, spi_crcen.last_bit), (6, 6));
+        assert_eq!(spi_crcen.value(&[0x40, 0, 0, 0]), 1);
+
+        assert_eq!(RegisterInfo::by_name("DEV_ID").unwrap().access, Access::RO);
+        assert_eq!(RegisterInfo::by_name("NOT_A_REGISTER"), None);
+
+        for register in REGISTERS {
+            assert_eq!(RegisterInfo::by_name(register.name), Some(register));
+            for field in register.fields {
+                assert!(field.first_bit <= field.last_bit);
+                assert!(field.last_bit < register.len * 8, "{}", field.name);
+            }
+        }
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_modify_masked() {
+        let spi = SpiMock::new(&[
+            // A single byte at the end of SYS_ENABLE
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC1, 0x05, 0xFF, 0x08]),
+            SpiTransaction::transaction_end(),
+            // Three bytes, the 32-bit window is moved into the register
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![
+                0xC0, 0xFB, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x20, 0x00, 0x08,
+            ]),
+            SpiTransaction::transaction_end(),
+            // Six bytes, falls back to read-modify-write
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(
+                vec![0x40, 0xF0, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10],
+            ),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![0xC0, 0xF0, 0x80, 0x01, 0x00, 0x00, 0x00, 0x00]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000::new(spi);
+
+        dw3000
+            .sys_enable()
+            .modify_masked(|w| w.spi_err_en(1))
+            .await
+            .unwrap();
+        dw3000
+            .sys_enable()
+            .modify_masked(|w| w.arfe_en(1).spi_err_en(1))
+            .await
+            .unwrap();
+        dw3000
+            .sys_enable()
+            .modify_masked(|w| w.txfrs_en(1).cca_fail_en(0))
+            .await
+            .unwrap();
+
+        dw3000.spi.done();
+    }
+
+    #[maybe_async::test(not(feature = "async"), async(all(feature = "async"), tokio::test))]
+    async fn test_read_crc_mismatch() {
+        let spi = SpiMock::new(&[
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x40, 0x60, 0], vec![0, 0, 0x12]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::transfer_in_place(vec![0x40, 0x60, 0], vec![0, 0, 0x34]),
+            SpiTransaction::transaction_end(),
+        ]);
+
+        let mut dw3000 = DW3000::new(spi);
+        dw3000.set_spi_crc(true);
+
+        let expected = crc8(0, &[0x40, 0x60, 0x12]);
+        match dw3000.spi_rd_crc().read().await {
+            Err(Error::Crc {
+                expected: e,
+                actual,
+            }) => {
+                assert_eq!(e, expected);
+                assert_eq!(actual, 0x34);
+            }
+            _ => panic!("CRC mismatch not detected"),
+        }
+
+        dw3000.spi.done();
+    }
+}
@@ -0,0 +1,300 @@
+//! Decoder for captured SPI transactions
+//!
+//! Turns the raw bytes of an SPI transaction, as seen on the bus by a logic
+//! analyzer or a wrapper around the SPI device, into a register access or a
+//! fast command. This makes it possible to compare the traffic of this driver
+//! with traces of other drivers, register by register.
+//!
+//! See `src/bin/dw3000-trace.rs` for a tool that annotates logic analyzer
+//! exports.
+
+use core::fmt;
+
+#[cfg(feature = "defmt")]
+use defmt::Format;
+
+use super::RegisterInfo;
+use crate::FastCommand;
+
+/// A decoded SPI transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transaction<'a> {
+    /// A fast command
+    FastCommand(FastCommand),
+    /// A register access
+    Register(RegisterAccess<'a>),
+}
+
+/// A decoded register access
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterAccess<'a> {
+    /// The register file ID
+    pub id: u8,
+    /// The address within the register file
+    pub sub_id: u8,
+    /// The register at the accessed address, if it is part of the register
+    /// map
+    pub register: Option<&'static RegisterInfo>,
+    /// The kind of access
+    pub kind: AccessKind,
+    /// The data that was read (from MISO) or written (from MOSI)
+    ///
+    /// Empty for masked writes.
+    pub data: &'a [u8],
+}
+
+/// The kind of a register access
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub enum AccessKind {
+    /// A read
+    Read,
+    /// A write
+    Write,
+    /// A masked write, which ANDs the register with `and_mask`, then ORs it
+    /// with `or_mask`
+    MaskedWrite {
+        /// The width of the masks in bytes (1, 2 or 4)
+        width: usize,
+        /// The AND mask
+        and_mask: u32,
+        /// The OR mask
+        or_mask: u32,
+    },
+}
+
+/// An error that can occur when decoding a transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+pub enum DecodeError {
+    /// The transaction is empty
+    Empty,
+    /// The transaction ends within the header
+    TruncatedHeader,
+    /// The transaction ends within the masks of a masked write
+    TruncatedMasks,
+    /// The fast command is not known
+    UnknownFastCommand(u8),
+}
+
+/// Decodes an SPI transaction
+///
+/// `mosi` holds the bytes sent by the host, `miso` the bytes sent by the
+/// DW3000 during the same transaction. `miso` is only needed to decode the
+/// data of reads, and may be empty otherwise. Set `crc` if the transaction
+/// was captured in SPI CRC mode, where every write ends with a CRC byte,
+/// which is then left out of the data.
+///
+/// All header formats are supported: fast commands, short (1-octet)
+/// addresses, full (2-octet) addresses and masked writes.
+pub fn decode<'a>(
+    mosi: &'a [u8],
+    miso: &'a [u8],
+    crc: bool,
+) -> Result<Transaction<'a>, DecodeError> {
+    let first = *mosi.first().ok_or(DecodeError::Empty)?;
+
+    let write = first & 0x80 != 0;
+    let long = first & 0x40 != 0;
+    let id = (first & 0x3e) >> 1;
+
+    if !long {
+        if first & 0x01 != 0 {
+            return FastCommand::try_from(id)
+                .map(Transaction::FastCommand)
+                .map_err(|_| DecodeError::UnknownFastCommand(id));
+        }
+
+        return Ok(register_access(id, 0, write, 1, mosi, miso, crc));
+    }
+
+    let second = *mosi.get(1).ok_or(DecodeError::TruncatedHeader)?;
+    let sub_id = ((first & 0x01) << 6) | (second >> 2);
+
+    let width = match second & 0x03 {
+        0 => return Ok(register_access(id, sub_id, write, 2, mosi, miso, crc)),
+        mode => 1 << (mode - 1),
+    };
+
+    let masks = mosi
+        .get(2..2 + 2 * width)
+        .ok_or(DecodeError::TruncatedMasks)?;
+    let (and_mask, or_mask) = masks.split_at(width);
+
+    Ok(Transaction::Register(RegisterAccess {
+        id,
+        sub_id,
+        register: RegisterInfo::by_address(id, sub_id),
+        kind: AccessKind::MaskedWrite {
+            width,
+            and_mask: le_u32(and_mask),
+            or_mask: le_u32(or_mask),
+        },
+        data: &[],
+    }))
+}
+
+fn register_access<'a>(
+    id: u8,
+    sub_id: u8,
+    write: bool,
+    header_len: usize,
+    mosi: &'a [u8],
+    miso: &'a [u8],
+    crc: bool,
+) -> Transaction<'a> {
+    let (kind, data) = if write && crc {
+        let data = mosi.split_last().map_or(mosi, |(_, data)| data);
+        (AccessKind::Write, data)
+    } else if write {
+        (AccessKind::Write, mosi)
+    } else {
+        (AccessKind::Read, miso)
+    };
+
+    Transaction::Register(RegisterAccess {
+        id,
+        sub_id,
+        register: RegisterInfo::by_address(id, sub_id),
+        kind,
+        data: data.get(header_len..).unwrap_or(&[]),
+    })
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| value << 8 | byte as u32)
+}
+
+impl fmt::Display for Transaction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transaction::FastCommand(command) => write!(f, "fast command {:?}", command),
+            Transaction::Register(access) => access.fmt(f),
+        }
+    }
+}
+
+/// Prints the access on the first line, followed by one line per field of the
+/// register that is fully covered by the data
+impl fmt::Display for RegisterAccess<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self.kind {
+            AccessKind::Read => "read",
+            AccessKind::Write => "write",
+            AccessKind::MaskedWrite { .. } => "modify",
+        };
+        let name = self.register.map_or("?", |register| register.name);
+        write!(
+            f,
+            "{} {} (0x{:02X}:0x{:02X})",
+            operation, name, self.id, self.sub_id
+        )?;
+
+        if let AccessKind::MaskedWrite {
+            width,
+            and_mask,
+            or_mask,
+        } = self.kind
+        {
+            let digits = 2 * width;
+            return write!(f, " & 0x{:0digits$x} | 0x{:0digits$x}", and_mask, or_mask);
+        }
+
+        write!(f, " = 0x")?;
+        for byte in self.data.iter().rev() {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        let fields = self.register.map_or(&[][..], |register| register.fields);
+        for field in fields {
+            if field.last_bit < self.data.len() * 8 {
+                write!(f, "\n    {} = {:#x}", field.name, field.value(self.data))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_headers() {
+        // Fast command, as sent by `ll::DW3000::fast_command`
+        assert_eq!(
+            decode(&[0x83], &[], false),
+            Ok(Transaction::FastCommand(FastCommand::CMD_TX))
+        );
+        assert_eq!(
+            decode(&[0xBF], &[], false),
+            Err(DecodeError::UnknownFastCommand(0x1F))
+        );
+
+        // Full address read of SYS_STATUS
+        let read = decode(&[0x41, 0x10, 0, 0], &[0, 0, 0x02, 0x00], false).unwrap();
+        let Transaction::Register(read) = read else {
+            panic!("not a register access");
+        };
+        assert_eq!((read.id, read.sub_id), (0x00, 0x44));
+        assert_eq!(read.register.unwrap().name, "SYS_STATUS");
+        assert_eq!(read.kind, AccessKind::Read);
+        assert_eq!(read.data, &[0x02, 0x00]);
+
+        // Short address write of TX_BUFFER, which isn't in the register map
+        let Ok(Transaction::Register(write)) = decode(&[0xA8, 0xAB], &[], false) else {
+            panic!("not a register access");
+        };
+        assert_eq!((write.id, write.sub_id), (0x14, 0x00));
+        assert_eq!(write.register, None);
+        assert_eq!(write.data, &[0xAB]);
+
+        // The same write in SPI CRC mode, followed by the CRC
+        let Ok(Transaction::Register(write)) = decode(&[0xA8, 0xAB, 0xE8], &[], true) else {
+            panic!("not a register access");
+        };
+        assert_eq!(write.data, &[0xAB]);
+
+        // 16-bit masked write of SYS_CFG
+        let Ok(Transaction::Register(modify)) =
+            decode(&[0xC0, 0x42, 0xBF, 0xFF, 0x40, 0x00], &[], false)
+        else {
+            panic!("not a register access");
+        };
+        assert_eq!(
+            modify.kind,
+            AccessKind::MaskedWrite {
+                width: 2,
+                and_mask: 0xFFBF,
+                or_mask: 0x0040,
+            }
+        );
+        assert_eq!(
+            decode(&[0xC0, 0x43, 0xFF], &[], false),
+            Err(DecodeError::TruncatedMasks)
+        );
+        assert_eq!(
+            decode(&[0x40], &[], false),
+            Err(DecodeError::TruncatedHeader)
+        );
+        assert_eq!(decode(&[], &[], false), Err(DecodeError::Empty));
+    }
+
+    #[test]
+    fn display() {
+        let write = decode(&[0xC0, 0x40, 0x88, 0x06, 0x00, 0x00], &[], false).unwrap();
+        let text = write.to_string();
+        assert!(text.starts_with("write SYS_CFG (0x00:0x10) = 0x00000688\n    ffen = 0x0\n"));
+        assert!(text.contains("\n    phr_mode = 0x0\n"));
+
+        let modify = decode(&[0xC0, 0x41, 0xBF, 0x40], &[], false).unwrap();
+        assert_eq!(
+            modify.to_string(),
+            "modify SYS_CFG (0x00:0x10) & 0xbf | 0x40"
+        );
+    }
+}
@@ -155,6 +155,7 @@ impl Sub<Instant> for Instant {
 ///
 /// Internally uses the same 40-bit timestamps that the DW3000 uses.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
 #[repr(C)]
 pub struct Duration(u64);
 
@@ -213,6 +214,72 @@ impl Duration {
     }
 }
 
+/// A signed duration in DW3000 system time units
+///
+/// Used for values that can be negative, like the time difference of arrival
+/// between the two CIRs of a received frame. The unit is the same as for
+/// [`Duration`], 1/(128*499.2*10^6) seconds.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(Format))]
+#[repr(C)]
+pub struct SignedDuration(i64);
+
+impl SignedDuration {
+    /// Creates a new instance of `SignedDuration`
+    ///
+    /// The magnitude of the given value must fit in a 40-bit timestamp, so:
+    /// -(2^40 - 1) <= `value` <= 2^40 - 1
+    ///
+    /// Returns `Some(...)`, if `value` is within the valid range, `None` if it
+    /// isn't.
+    ///
+    /// # Example
+    ///
+    /// ``` rust
+    /// use dw3000_ng::time::{
+    ///     TIME_MAX,
+    ///     SignedDuration,
+    /// };
+    ///
+    /// let valid_duration   = SignedDuration::new(-(TIME_MAX as i64));
+    /// let invalid_duration = SignedDuration::new(TIME_MAX as i64 + 1);
+    ///
+    /// assert!(valid_duration.is_some());
+    /// assert!(invalid_duration.is_none());
+    /// ```
+    pub fn new(value: i64) -> Option<Self> {
+        if value.unsigned_abs() <= TIME_MAX {
+            Some(SignedDuration(value))
+        } else {
+            None
+        }
+    }
+
+    /// Creates a `SignedDuration` from the 41-bit two's complement value of
+    /// the `TDOA` register
+    pub(crate) fn from_tdoa(raw: u64) -> Self {
+        SignedDuration(((raw << 23) as i64) >> 23)
+    }
+
+    /// Returns the raw signed value
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns the magnitude of the duration
+    pub fn abs(&self) -> Duration {
+        Duration(self.0.unsigned_abs())
+    }
+}
+
+impl From<Duration> for SignedDuration {
+    fn from(duration: Duration) -> Self {
+        // `Duration` is guaranteed to contain a 40-bit number, which always
+        // fits in an `i64`.
+        SignedDuration(duration.value() as i64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,4 +334,16 @@ mod tests {
 
         assert_eq!(duration.value(), 383);
     }
+
+    #[test]
+    fn signed_duration_from_tdoa() {
+        let duration = SignedDuration::from_tdoa(0x1FF_FFFF_FFFF);
+
+        assert_eq!(duration.value(), -1);
+        assert_eq!(duration.abs().value(), 1);
+
+        let duration = SignedDuration::from_tdoa(0x0FF_FFFF_FFFF);
+
+        assert_eq!(duration.value(), 0xFF_FFFF_FFFF);
+    }
 }